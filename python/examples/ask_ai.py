@@ -14,12 +14,12 @@ def main() -> None:
     args = parser.parse_args()
 
     km = KinicMemories(args.identity, ic=args.ic)
-    prompt, answer = km.ask_ai(args.memory_id, args.query, top_k=args.top_k, language=args.language)
+    result = km.ask_ai(args.memory_id, args.query, top_k=args.top_k, language=args.language)
 
-    print("Prompt sent to LLM:\n")
-    print(prompt)
+    print(f"Prompt sent to LLM (top {result.top_k_used} of {result.context_count} hits):\n")
+    print(result.prompt)
     print("\nAnswer:\n")
-    print(answer)
+    print(result.response)
 
 
 if __name__ == "__main__":
@@ -4,9 +4,9 @@ Based on the model card instructions:
 https://huggingface.co/LiquidAI/LFM2-ColBERT-350M
 """
 
-import json
-from typing import Iterable, List, Sequence, Tuple
+from typing import Iterable, List, Tuple
 
+import numpy as np
 from kinic_py import KinicMemories
 from pylate import indexes, models, rank, retrieve
 
@@ -34,8 +34,9 @@ def retrieval_and_rerank(model: models.ColBERT, kinic: KinicMemories) -> None:
     for doc_id, doc_text, token_embeddings in zip(
         documents_ids, documents, documents_embeddings
     ):
-        for token_embedding in as_matrix(token_embeddings):
-            kinic.insert_raw(memory_id, doc_id, doc_text, token_embedding)
+        matrix = as_matrix(token_embeddings)
+        rows = len(matrix)
+        kinic.insert_raw_many(memory_id, [doc_id] * rows, [doc_text] * rows, matrix)
 
     queries = ["query for document 3", "query for document 1"]
     queries_embeddings = model.encode(
@@ -53,47 +54,36 @@ def retrieval_and_rerank(model: models.ColBERT, kinic: KinicMemories) -> None:
         print("reranked:", reranked)
 
 
-def as_matrix(embeddings: object) -> List[List[float]]:
-    if hasattr(embeddings, "tolist"):
-        return embeddings.tolist()
-    if isinstance(embeddings, list):
-        return embeddings
-    return list(embeddings)
+def as_matrix(embeddings: object) -> np.ndarray:
+    # PyLate may hand back tensors. The bindings read float32 arrays straight from their
+    # buffer, though the values are still copied into Rust.
+    if hasattr(embeddings, "cpu"):
+        embeddings = embeddings.cpu().numpy()
+    return np.asarray(embeddings, dtype=np.float32)
 
 
 def collect_candidate_tags(
     kinic: KinicMemories,
     memory_id: str,
-    query_vectors: Sequence[Sequence[float]],
+    query_vectors: np.ndarray,
     *,
     per_vector_limit: int = 5,
 ) -> List[str]:
     tags = []
     seen = set()
-    for vector in query_vectors:
-        results = kinic.search_raw(memory_id, vector)
-        for score, payload in results[:per_vector_limit]:
-            tag = parse_tag(payload)
+    for results in kinic.search_raw_many(memory_id, query_vectors):
+        for hit in results[:per_vector_limit]:
+            tag = hit.tag
             if tag and tag not in seen:
                 seen.add(tag)
                 tags.append(tag)
     return tags
 
 
-def parse_tag(payload: str) -> str | None:
-    try:
-        data = json.loads(payload)
-    except json.JSONDecodeError:
-        return None
-    if isinstance(data, dict):
-        return data.get("tag")
-    return None
-
-
 def rerank_by_maxsim(
     kinic: KinicMemories,
     memory_id: str,
-    query_vectors: Sequence[Sequence[float]],
+    query_vectors: np.ndarray,
     candidate_tags: Iterable[str],
 ) -> List[Tuple[str, float]]:
     # Fetch each candidate's bag-of-embeddings from the memory canister.
@@ -44,8 +44,8 @@ def main() -> None:
 
     results = km.search(memory_id, args.tag.replace("_", " "))
     print("Search results for tag terms:")
-    for score, payload in results:
-        print(f"- [{score:.4f}] {payload}")
+    for hit in results:
+        print(f"- [{hit.score:.4f}] {hit.sentence}")
 
 
 if __name__ == "__main__":
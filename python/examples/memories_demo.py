@@ -28,8 +28,8 @@ def main() -> None:
     if args.memory_id:
         print(f"Searching {args.memory_id}...")
         results = km.search(args.memory_id, "Hello")
-        for score, text in results:
-            print(f"- [{score:.4f}] {text}")
+        for hit in results:
+            print(f"- [{hit.score:.4f}] {hit.tag}: {hit.sentence}")
     else:
         canister_id = km.create("Python demo", "Created from kinic_py example")
         print(f"Created new memory canister: {canister_id}")
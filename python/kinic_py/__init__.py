@@ -3,6 +3,19 @@
 from __future__ import annotations
 
 from . import _lib as native
+from ._lib import (
+    AskAiResult,
+    AuthError,
+    CanisterRejectError,
+    Client,
+    EmbeddingError,
+    KinicError,
+    LedgerError,
+    NetworkError,
+    SearchHit,
+    ValidationError,
+    login,
+)
 from .memories import (
     KinicMemories,
     create_memory,
@@ -10,39 +23,64 @@ from .memories import (
     insert_markdown,
     insert_markdown_file,
     insert_raw,
+    insert_raw_many,
     insert_pdf,
     insert_pdf_file,
     insert_text,
     list_memories,
     search_memories,
     search_raw,
+    search_raw_many,
     tagged_embeddings,
     ask_ai,
     get_balance,
     update_instance,
     reset_memory,
     add_user,
+    insert_markdown_async,
+    insert_raw_async,
+    search_memories_async,
+    tagged_embeddings_async,
+    ask_ai_async,
 )
 
 __all__ = [
+    "Client",
     "KinicMemories",
+    "SearchHit",
+    "AskAiResult",
     "create_memory",
     "insert_file",
     "insert_markdown",
     "insert_markdown_file",
     "insert_raw",
+    "insert_raw_many",
     "insert_pdf_file",
     "insert_pdf",
     "insert_text",
     "list_memories",
     "search_memories",
     "search_raw",
+    "search_raw_many",
     "tagged_embeddings",
     "ask_ai",
     "get_balance",
     "update_instance",
     "reset_memory",
     "add_user",
+    "login",
+    "insert_markdown_async",
+    "insert_raw_async",
+    "search_memories_async",
+    "tagged_embeddings_async",
+    "ask_ai_async",
+    "KinicError",
+    "AuthError",
+    "NetworkError",
+    "CanisterRejectError",
+    "EmbeddingError",
+    "ValidationError",
+    "LedgerError",
     "native",
     "__version__",
 ]
@@ -3,43 +3,101 @@
 from __future__ import annotations
 
 import warnings
-from typing import List, Sequence, Tuple
+from typing import TYPE_CHECKING, List, Sequence, Union
 
 from . import _lib as native
+from ._lib import AskAiResult, SearchHit
 
-ScoreResult = Sequence[Tuple[float, str]]
+if TYPE_CHECKING:
+    import numpy as np
+
+# A 1-D NumPy array (any float dtype) or a plain sequence of floats.
+Vector = Union["np.ndarray", Sequence[float]]
+# A 2-D NumPy array with one embedding per row, or a sequence of vectors.
+Matrix = Union["np.ndarray", Sequence[Sequence[float]]]
 
 
 class KinicMemories:
-    """Stateful helper that mirrors the Rust CLI behavior."""
+    """Stateful helper that mirrors the Rust CLI behavior.
+
+    The agent is built on first use and reused for every later call.
+    """
 
-    def __init__(self, identity: str, *, ic: bool = False) -> None:
+    def __init__(
+        self,
+        identity: str | None = None,
+        *,
+        ic: bool = False,
+        client: native.Client | None = None,
+    ) -> None:
+        if identity is None and client is None:
+            raise ValueError("either `identity` or `client` must be provided")
         self.identity = identity
         self.ic = ic
+        self._client = client
+
+    @classmethod
+    def from_ii(
+        cls,
+        identity_path: str | None = None,
+        *,
+        session: str | None = None,
+        ic: bool = False,
+    ) -> KinicMemories:
+        """Use a stored Internet Identity delegation (create one with kinic_py.login())."""
+        return cls(ic=ic, client=native.Client.from_ii(identity_path, session=session, ic=ic))
+
+    @classmethod
+    def from_pem(cls, pem: str, *, ic: bool = False) -> KinicMemories:
+        """Use a PEM private key given as text."""
+        return cls(ic=ic, client=native.Client.from_pem(pem, ic=ic))
+
+    @classmethod
+    def from_pem_file(cls, path: str, *, ic: bool = False) -> KinicMemories:
+        """Use the PEM private key stored at path."""
+        return cls(ic=ic, client=native.Client.from_pem_file(path, ic=ic))
+
+    @classmethod
+    def anonymous(cls, *, ic: bool = False) -> KinicMemories:
+        """Unauthenticated access to memories readable by anonymous."""
+        return cls(ic=ic, client=native.Client.anonymous(ic=ic))
+
+    @property
+    def client(self) -> native.Client:
+        """Connected native client, created on first access."""
+        if self._client is None:
+            self._client = native.Client(self.identity, ic=self.ic)
+        return self._client
 
     def create(self, name: str, description: str) -> str:
         """Deploy a new memory canister."""
-        return create_memory(self.identity, name, description, ic=self.ic)
+        return self.client.create_memory(name, description)
 
     def list(self) -> List[str]:
         """List deployed memories."""
-        return list_memories(self.identity, ic=self.ic)
+        return self.client.list_memories()
 
     def insert_markdown(self, memory_id: str, tag: str, text: str) -> int:
         """Insert markdown text directly."""
-        return insert_markdown(self.identity, memory_id, tag, text, ic=self.ic)
+        return self.client.insert_memory(memory_id, tag, text=text)
 
     def insert_markdown_file(self, memory_id: str, tag: str, path: str) -> int:
         """Insert markdown loaded from disk."""
-        return insert_markdown_file(self.identity, memory_id, tag, path, ic=self.ic)
+        return self.client.insert_memory(memory_id, tag, file_path=path)
 
-    def insert_raw(self, memory_id: str, tag: str, text: str, embedding: Sequence[float]) -> int:
+    def insert_raw(self, memory_id: str, tag: str, text: str, embedding: Vector) -> int:
         """Insert a precomputed embedding with text."""
-        return insert_raw(self.identity, memory_id, tag, text, embedding, ic=self.ic)
+        return self.client.insert_memory_raw(memory_id, tag, text, embedding)
+
+    def insert_raw_many(
+        self, memory_id: str, tags: Sequence[str], texts: Sequence[str], matrix: Matrix
+    ) -> int:
+        """Insert one entry per matrix row, with tags[i] and texts[i] for row i."""
+        return self.client.insert_memory_raw_many(memory_id, list(tags), list(texts), matrix)
 
     def insert_pdf_file(self, memory_id: str, tag: str, path: str) -> int:
         """Convert a PDF to markdown and insert it."""
-        return insert_pdf_file(self.identity, memory_id, tag, path, ic=self.ic)
+        return self.client.insert_memory_pdf(memory_id, tag, path)
 
     def insert_pdf(self, memory_id: str, tag: str, path: str) -> int:
         """Deprecated: use insert_pdf_file instead."""
@@ -56,17 +114,21 @@ class KinicMemories:
         warnings.warn("insert_file is deprecated; use insert_markdown_file", DeprecationWarning, stacklevel=2)
         return self.insert_markdown_file(memory_id, tag, path)
 
-    def search(self, memory_id: str, query: str) -> ScoreResult:
+    def search(self, memory_id: str, query: str) -> List[SearchHit]:
         """Search the specified memory canister."""
-        return search_memories(self.identity, memory_id, query, ic=self.ic)
+        return self.client.search_memories(memory_id, query)
 
-    def search_raw(self, memory_id: str, embedding: Sequence[float]) -> ScoreResult:
+    def search_raw(self, memory_id: str, embedding: Vector) -> List[SearchHit]:
         """Search using a precomputed embedding."""
-        return search_raw(self.identity, memory_id, embedding, ic=self.ic)
+        return self.client.search_memories_raw(memory_id, embedding)
+
+    def search_raw_many(self, memory_id: str, matrix: Matrix) -> List[List[SearchHit]]:
+        """Search once per matrix row; returns one result list per row."""
+        return self.client.search_memories_raw_many(memory_id, matrix)
 
-    def tagged_embeddings(self, memory_id: str, tag: str) -> List[List[float]]:
-        """Fetch all embeddings associated with a tag."""
-        return tagged_embeddings(self.identity, memory_id, tag, ic=self.ic)
+    def tagged_embeddings(self, memory_id: str, tag: str) -> np.ndarray:
+        """Fetch all embeddings associated with a tag as an (n, dim) float32 array."""
+        return self.client.tagged_embeddings(memory_id, tag)
 
     def ask_ai(
         self,
@@ -75,32 +137,54 @@ class KinicMemories:
         *,
         top_k: int | None = None,
         language: str | None = None,
-    ) -> tuple[str, str]:
-        """Run ask-ai (search + LLM) and return (prompt, answer)."""
-        return ask_ai(
-            self.identity,
-            memory_id,
-            query,
-            top_k=top_k,
-            language=language,
-            ic=self.ic,
-        )
+    ) -> AskAiResult:
+        """Run ask-ai (search + LLM); the result holds the prompt, answer and context hits."""
+        return self.client.ask_ai(memory_id, query, top_k=top_k, language=language)
 
     def balance(self) -> tuple[int, float]:
         """Return (base_units, kinic) balance for the current identity."""
-        return get_balance(self.identity, ic=self.ic)
+        return self.client.get_balance()
 
     def update(self, memory_id: str) -> None:
         """Trigger launcher update_instance for the memory canister."""
-        update_instance(self.identity, memory_id, ic=self.ic)
+        self.client.update_instance(memory_id)
 
     def reset(self, memory_id: str, dim: int) -> None:
         """Reset a memory canister and set embedding dimension."""
-        reset_memory(self.identity, memory_id, dim, ic=self.ic)
+        self.client.reset_memory(memory_id, dim)
 
     def add_user(self, memory_id: str, user_id: str, role: str) -> None:
         """Configure visibility: add a user (principal or 'anonymous') with a role (admin/writer/reader)."""
-        add_user(self.identity, memory_id, user_id, role, ic=self.ic)
+        self.client.add_user(memory_id, user_id, role)
+
+    async def insert_markdown_async(self, memory_id: str, tag: str, text: str) -> int:
+        """Awaitable insert_markdown."""
+        return await self.client.insert_memory_async(memory_id, tag, text=text)
+
+    async def insert_raw_async(
+        self, memory_id: str, tag: str, text: str, embedding: Vector
+    ) -> int:
+        """Awaitable insert_raw."""
+        return await self.client.insert_memory_raw_async(memory_id, tag, text, embedding)
+
+    async def search_async(self, memory_id: str, query: str) -> List[SearchHit]:
+        """Awaitable search."""
+        return await self.client.search_memories_async(memory_id, query)
+
+    async def tagged_embeddings_async(self, memory_id: str, tag: str) -> np.ndarray:
+        """Awaitable tagged_embeddings."""
+        return await self.client.tagged_embeddings_async(memory_id, tag)
+
+    async def ask_ai_async(
+        self,
+        memory_id: str,
+        query: str,
+        *,
+        top_k: int | None = None,
+        language: str | None = None,
+    ) -> AskAiResult:
+        """Awaitable ask_ai."""
+        return await self.client.ask_ai_async(memory_id, query, top_k=top_k, language=language)
 
 
 def create_memory(
@@ -144,11 +228,25 @@ def insert_raw(
     memory_id: str,
     tag: str,
     text: str,
-    embedding: Sequence[float],
+    embedding: Vector,
     *,
     ic: bool | None = None,
 ) -> int:
-    return native.insert_memory_raw(identity, memory_id, tag, text, list(embedding), ic=ic)
+    return native.insert_memory_raw(identity, memory_id, tag, text, embedding, ic=ic)
+
+
+def insert_raw_many(
+    identity: str,
+    memory_id: str,
+    tags: Sequence[str],
+    texts: Sequence[str],
+    matrix: Matrix,
+    *,
+    ic: bool | None = None,
+) -> int:
+    return native.insert_memory_raw_many(
+        identity, memory_id, list(tags), list(texts), matrix, ic=ic
+    )
 
 
 def insert_pdf_file(
@@ -204,18 +302,28 @@ def search_memories(
     query: str,
     *,
     ic: bool | None = None,
-) -> ScoreResult:
+) -> List[SearchHit]:
     return native.search_memories(identity, memory_id, query, ic=ic)
 
 
 def search_raw(
     identity: str,
     memory_id: str,
-    embedding: Sequence[float],
+    embedding: Vector,
+    *,
+    ic: bool | None = None,
+) -> List[SearchHit]:
+    return native.search_memories_raw(identity, memory_id, embedding, ic=ic)
+
+
+def search_raw_many(
+    identity: str,
+    memory_id: str,
+    matrix: Matrix,
     *,
     ic: bool | None = None,
-) -> ScoreResult:
-    return native.search_memories_raw(identity, memory_id, list(embedding), ic=ic)
+) -> List[List[SearchHit]]:
+    return native.search_memories_raw_many(identity, memory_id, matrix, ic=ic)
 
 
 def tagged_embeddings(
@@ -224,7 +332,7 @@ def tagged_embeddings(
     tag: str,
     *,
     ic: bool | None = None,
-) -> List[List[float]]:
+) -> np.ndarray:
     return native.tagged_embeddings(identity, memory_id, tag, ic=ic)
 
 
@@ -236,7 +344,7 @@ def ask_ai(
     top_k: int | None = None,
     language: str | None = None,
     ic: bool | None = None,
-) -> tuple[str, str]:
+) -> AskAiResult:
     return native.ask_ai(identity, memory_id, query, top_k=top_k, language=language, ic=ic)
 
 
@@ -267,3 +375,62 @@ def add_user(
     ic: bool | None = None,
 ) -> None:
     return native.add_user(identity, memory_id, user_id, role, ic=ic)
+
+
+async def insert_markdown_async(
+    identity: str,
+    memory_id: str,
+    tag: str,
+    text: str,
+    *,
+    ic: bool | None = None,
+) -> int:
+    return await native.insert_memory_async(identity, memory_id, tag, text=text, ic=ic)
+
+
+async def insert_raw_async(
+    identity: str,
+    memory_id: str,
+    tag: str,
+    text: str,
+    embedding: Vector,
+    *,
+    ic: bool | None = None,
+) -> int:
+    return await native.insert_memory_raw_async(
+        identity, memory_id, tag, text, embedding, ic=ic
+    )
+
+
+async def search_memories_async(
+    identity: str,
+    memory_id: str,
+    query: str,
+    *,
+    ic: bool | None = None,
+) -> List[SearchHit]:
+    return await native.search_memories_async(identity, memory_id, query, ic=ic)
+
+
+async def tagged_embeddings_async(
+    identity: str,
+    memory_id: str,
+    tag: str,
+    *,
+    ic: bool | None = None,
+) -> np.ndarray:
+    return await native.tagged_embeddings_async(identity, memory_id, tag, ic=ic)
+
+
+async def ask_ai_async(
+    identity: str,
+    memory_id: str,
+    query: str,
+    *,
+    top_k: int | None = None,
+    language: str | None = None,
+    ic: bool | None = None,
+) -> AskAiResult:
+    return await native.ask_ai_async(
+        identity, memory_id, query, top_k=top_k, language=language, ic=ic
+    )
@@ -4,17 +4,26 @@ use anyhow::Result;
 use ic_agent::{
     Agent, Identity,
     export::reqwest::Url,
-    identity::{BasicIdentity, Secp256k1Identity},
+    identity::{AnonymousIdentity, BasicIdentity, Secp256k1Identity},
 };
 
+use crate::error::KinicError;
+
 pub const KEYRING_SERVICE_NAME: &str = "internet_computer_identities";
 pub const KEYRING_IDENTITY_PREFIX: &str = "internet_computer_identity_";
 
+/// Identity resolved by [`AgentFactory`], along with a label for the key it came from.
+#[derive(Clone)]
+pub struct LoadedIdentity {
+    pub identity: Arc<dyn Identity>,
+    pub key_type: &'static str,
+}
+
 #[derive(Clone)]
 pub struct AgentFactory {
     use_mainnet: bool,
     identity_suffix: String,
-    identity_override: Option<Arc<dyn Identity>>,
+    identity_override: Option<LoadedIdentity>,
 }
 
 impl AgentFactory {
@@ -30,32 +39,58 @@ impl AgentFactory {
     where
         I: Identity + 'static,
     {
+        Self::with_loaded(
+            use_mainnet,
+            LoadedIdentity {
+                identity: Arc::new(identity),
+                key_type: "delegation",
+            },
+        )
+    }
+
+    /// Signs with the key in `pem_text` instead of a keyring entry.
+    pub fn from_pem(use_mainnet: bool, pem_text: &str) -> Result<Self> {
+        Ok(Self::with_loaded(use_mainnet, identity_from_pem(pem_text)?))
+    }
+
+    /// Sends unsigned calls; only memories that grant `anonymous` a role will accept them.
+    pub fn anonymous(use_mainnet: bool) -> Self {
+        Self::with_loaded(
+            use_mainnet,
+            LoadedIdentity {
+                identity: Arc::new(AnonymousIdentity),
+                key_type: "anonymous",
+            },
+        )
+    }
+
+    fn with_loaded(use_mainnet: bool, loaded: LoadedIdentity) -> Self {
         Self {
             use_mainnet,
             identity_suffix: String::new(),
-            identity_override: Some(Arc::new(identity)),
+            identity_override: Some(loaded),
         }
     }
 
+    /// Dfx identity name used for the keyring lookup; empty when an identity was injected.
+    pub fn identity_suffix(&self) -> &str {
+        &self.identity_suffix
+    }
+
+    /// Resolves the signing identity without contacting the network.
+    pub fn load_identity(&self) -> Result<LoadedIdentity> {
+        if let Some(loaded) = &self.identity_override {
+            return Ok(loaded.clone());
+        }
+
+        let pem_bytes = load_pem_from_keyring(&self.identity_suffix)?;
+        let pem_text = String::from_utf8(pem_bytes)?;
+        identity_from_pem(&pem_text)
+    }
+
     pub async fn build(&self) -> Result<Agent> {
-        let builder = if let Some(identity) = &self.identity_override {
-            Agent::builder().with_arc_identity(identity.clone())
-        } else {
-            let pem_bytes = load_pem_from_keyring(&self.identity_suffix)?;
-            let pem_text = String::from_utf8(pem_bytes.clone())?;
-            let pem = pem::parse(pem_text.as_bytes())?;
-            match pem.tag() {
-                "PRIVATE KEY" => {
-                    let identity = BasicIdentity::from_pem(Cursor::new(pem_text.clone()))?;
-                    Agent::builder().with_identity(identity)
-                }
-                "EC PRIVATE KEY" => {
-                    let identity = Secp256k1Identity::from_pem(Cursor::new(pem_text.clone()))?;
-                    Agent::builder().with_identity(identity)
-                }
-                _ => anyhow::bail!("Unsupported PEM tag: {}", pem.tag()),
-            }
-        };
+        let loaded = self.load_identity()?;
+        let builder = Agent::builder().with_arc_identity(loaded.identity);
 
         let url = if self.use_mainnet {
             "https://ic0.app"
@@ -72,17 +107,39 @@ impl AgentFactory {
     }
 }
 
+/// Parses an Ed25519 (`PRIVATE KEY`) or secp256k1 (`EC PRIVATE KEY`) PEM, as written by dfx.
+fn identity_from_pem(pem_text: &str) -> Result<LoadedIdentity> {
+    let pem = pem::parse(pem_text.as_bytes())
+        .map_err(|e| KinicError::Auth(format!("Failed to parse PEM: {e}")))?;
+    let (identity, key_type): (Arc<dyn Identity>, _) = match pem.tag() {
+        "PRIVATE KEY" => (
+            Arc::new(BasicIdentity::from_pem(Cursor::new(pem_text))?),
+            "Ed25519",
+        ),
+        "EC PRIVATE KEY" => (
+            Arc::new(Secp256k1Identity::from_pem(Cursor::new(pem_text))?),
+            "secp256k1",
+        ),
+        _ => anyhow::bail!(KinicError::Auth(format!(
+            "Unsupported PEM tag: {}",
+            pem.tag()
+        ))),
+    };
+    Ok(LoadedIdentity { identity, key_type })
+}
+
 fn load_pem_from_keyring(suffix: &str) -> anyhow::Result<Vec<u8>> {
     let account = format!("{KEYRING_IDENTITY_PREFIX}{suffix}");
     let entry = keyring::Entry::new(KEYRING_SERVICE_NAME, &account)?;
     let encoded_pem = entry.get_password().map_err(|e| {
         let msg = format!("{e:?}");
         if msg.contains("-67671") || msg.contains("errSecInteractionNotAllowed") {
-            anyhow::anyhow!(
+            KinicError::Auth(
                 "macOS keychain returned -67671 (errSecInteractionNotAllowed). This is a known bug when using the x86 build of dfx; please install and use the arm64 build instead. See more detail: https://github.com/dfinity/sdk/blob/0.28.0/docs/migration/dfx-0.28.0-migration-guide.md"
+                    .to_string(),
             )
         } else {
-            anyhow::anyhow!("Keychain Error: {msg}")
+            KinicError::Auth(format!("Keychain Error: {msg}"))
         }
     })?;
     Ok(hex::decode(encoded_pem)?)
@@ -7,7 +7,7 @@ async fn main() -> ExitCode {
     let _ = dotenvy::dotenv();
     if let Err(e) = kinic_cli::run().await {
         eprintln!("{e:?}");
-        return ExitCode::from(1);
+        return ExitCode::from(kinic_cli::ErrorKind::of(&e).exit_code());
     }
     ExitCode::SUCCESS
 }
@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use clap::{ArgGroup, Args, Parser, Subcommand};
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -47,13 +47,35 @@ pub struct GlobalOpts {
         help = "Path to identity.json (default: ~/.config/kinic/identity.json)"
     )]
     pub identity_path: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        conflicts_with = "identity_path",
+        help = "Named Internet Identity session (stored in ~/.config/kinic/sessions/NAME.json)"
+    )]
+    pub session: Option<String>,
+
+    #[arg(
+        long,
+        requires = "ii",
+        help = "Re-run the Internet Identity login when the delegation is missing or about to expire"
+    )]
+    pub relogin: bool,
+
+    #[arg(
+        long,
+        value_name = "0xHEX|INDEX|NAME",
+        help = "Ledger subaccount for balance, transfers, allowances and history (decimal index, 0x-prefixed or 64-char hex, or a name from config.json); not supported by create"
+    )]
+    pub subaccount: Option<String>,
 }
 
 #[derive(Subcommand, Debug)]
 pub enum Command {
     #[command(about = "Deploy a new memory canister via the launcher")]
     Create(CreateArgs),
-    #[command(about = "List deployed memories and their principals")]
+    #[command(about = "List deployed memories with their state, name and description")]
     List(ListArgs),
     #[command(about = "Insert text into an existing memory canister")]
     Insert(InsertArgs),
@@ -71,16 +93,36 @@ pub enum Command {
     TaggedEmbeddings(TaggedEmbeddingsArgs),
     #[command(about = "Manage Kinic CLI configuration")]
     Config(ConfigArgs),
+    #[command(about = "List and manage users and roles of a memory canister")]
+    Acl(AclArgs),
     #[command(about = "Update a memory canister instance")]
     Update(UpdateArgs),
     #[command(about = "Reset a memory canister and set embedding dimension")]
     Reset(ResetArgs),
     #[command(about = "Check KINIC token balance for the current identity")]
     Balance(BalanceArgs),
+    #[command(about = "Transfer KINIC tokens to another principal or account")]
+    Transfer(TransferArgs),
+    #[command(about = "Inspect or revoke ICRC-2 allowances granted by the current identity")]
+    Allowance(AllowanceArgs),
+    #[command(about = "Show KINIC ledger transactions involving the current identity")]
+    History(HistoryArgs),
     #[command(about = "Ask Kinic AI using memory search results (LLM placeholder)")]
     AskAi(AskAiArgs),
     #[command(about = "Login via Internet Identity and store a delegation")]
     Login(LoginArgs),
+    #[command(about = "Show the principal, account and identity source in use (offline)")]
+    Whoami(WhoamiArgs),
+    #[command(about = "Delete a stored Internet Identity delegation and its session key")]
+    Logout(LogoutArgs),
+    #[command(about = "Manage stored Internet Identity sessions")]
+    Sessions(SessionsArgs),
+    #[command(
+        about = "Serve memory operations and OpenAI-style RAG endpoints as a local JSON HTTP API"
+    )]
+    Serve(ServeArgs),
+    #[command(about = "Run a Model Context Protocol (MCP) server over stdio for AI agents")]
+    Mcp(McpArgs),
 }
 
 #[derive(Args, Debug)]
@@ -90,10 +132,63 @@ pub struct CreateArgs {
 
     #[arg(long, required = true, help = "Short description for the new memory")]
     pub description: String,
+
+    #[arg(
+        long,
+        default_value_t = true,
+        action = clap::ArgAction::Set,
+        value_name = "BOOL",
+        help = "Wait until the new memory is running before returning"
+    )]
+    pub wait: bool,
+
+    #[arg(
+        long,
+        default_value_t = 300,
+        value_name = "SECS",
+        help = "Maximum time to wait for the memory to become ready"
+    )]
+    pub wait_timeout: u64,
+
+    #[arg(
+        long,
+        help = "Show balance, price, fees and the allowance to grant without sending any update calls"
+    )]
+    pub dry_run: bool,
+
+    #[arg(
+        short,
+        long,
+        help = "Skip the confirmation prompt before approving tokens and deploying"
+    )]
+    pub yes: bool,
+
+    #[arg(
+        long,
+        help = "If deployment fails, revoke the launcher's leftover allowance without asking (costs one ledger fee)"
+    )]
+    pub revoke_on_failure: bool,
 }
 
 #[derive(Args, Debug)]
-pub struct ListArgs {}
+pub struct ListArgs {
+    #[arg(
+        long,
+        value_enum,
+        value_name = "STATE",
+        help = "Only show memories in the given deployment state"
+    )]
+    pub state: Option<MemoryState>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemoryState {
+    Pending,
+    Creation,
+    Installation,
+    SettingUp,
+    Running,
+}
 
 #[derive(Args, Debug)]
 #[command(group = ArgGroup::new("insert_input").required(true).args(["text", "file_path"]))]
@@ -230,11 +325,118 @@ pub struct ConfigArgs {
         long,
         value_names = ["USER_ID", "ROLE"],
         num_args = 2,
-        help = "Add a user with role to the Kinic CLI config (placeholder)"
+        help = "Add a user with role to the memory canister (see also `acl`)"
     )]
     pub add_user: Option<Vec<String>>,
 }
 
+#[derive(Args, Debug)]
+pub struct AclArgs {
+    #[command(subcommand)]
+    pub command: AclCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AclCommand {
+    #[command(about = "List users and their roles")]
+    List(AclListArgs),
+    #[command(about = "Grant a role to a user that has no access yet")]
+    Add(AclUserArgs),
+    #[command(
+        about = "Change the role of an existing user (re-grants the role; the canister's add_new_user overwrites the old one)"
+    )]
+    SetRole(AclSetRoleArgs),
+    #[command(about = "Remove a user's access")]
+    Remove(AclRemoveArgs),
+    #[command(about = "Apply a YAML policy file, previewing the changes first")]
+    Apply(AclApplyArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct AclListArgs {
+    #[arg(
+        long,
+        required = true,
+        help = "Principal of the target memory canister"
+    )]
+    pub memory_id: String,
+}
+
+#[derive(Args, Debug)]
+pub struct AclUserArgs {
+    #[arg(
+        long,
+        required = true,
+        help = "Principal of the target memory canister"
+    )]
+    pub memory_id: String,
+
+    #[arg(help = "User principal, or `anonymous`")]
+    pub user: String,
+
+    #[arg(help = "Role to grant: admin, writer or reader")]
+    pub role: String,
+}
+
+#[derive(Args, Debug)]
+pub struct AclSetRoleArgs {
+    #[command(flatten)]
+    pub target: AclUserArgs,
+
+    #[arg(
+        long,
+        help = "Allow changes that remove or demote the current identity or leave no admin"
+    )]
+    pub allow_self_demotion: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct AclRemoveArgs {
+    #[arg(
+        long,
+        required = true,
+        help = "Principal of the target memory canister"
+    )]
+    pub memory_id: String,
+
+    #[arg(help = "User principal, or `anonymous`")]
+    pub user: String,
+
+    #[arg(short, long, help = "Skip the confirmation prompt")]
+    pub yes: bool,
+
+    #[arg(
+        long,
+        help = "Allow changes that remove or demote the current identity or leave no admin"
+    )]
+    pub allow_self_demotion: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct AclApplyArgs {
+    #[arg(
+        long,
+        required = true,
+        help = "Principal of the target memory canister"
+    )]
+    pub memory_id: String,
+
+    #[arg(long, value_name = "FILE", help = "YAML policy mapping users to roles")]
+    pub file: PathBuf,
+
+    #[arg(long, help = "Also remove users that are not listed in the policy")]
+    pub prune: bool,
+
+    #[arg(short, long, help = "Skip the confirmation prompt")]
+    pub yes: bool,
+
+    #[arg(
+        long,
+        help = "Allow changes that remove or demote the current identity or leave no admin"
+    )]
+    pub allow_self_demotion: bool,
+}
+
 #[derive(Args, Debug)]
 pub struct UpdateArgs {
     #[arg(
@@ -256,10 +458,140 @@ pub struct ResetArgs {
 
     #[arg(long, required = true, help = "Embedding dimension to set after reset")]
     pub dim: usize,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Export every record to FILE (JSON lines) before resetting; small memories only, the export is one unpaginated query"
+    )]
+    pub backup_to: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Reset even if --dim differs from the embedding API's dimension"
+    )]
+    pub force: bool,
+
+    #[arg(short, long, help = "Skip the confirmation prompt")]
+    pub yes: bool,
 }
 
 #[derive(Args, Debug)]
-pub struct BalanceArgs {}
+pub struct BalanceArgs {
+    #[arg(
+        long,
+        help = "Show the default account and every subaccount named in config.json, with a total"
+    )]
+    pub all_subaccounts: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct TransferArgs {
+    #[arg(
+        long,
+        required = true,
+        value_name = "ACCOUNT",
+        help = "Recipient principal, optionally with .<subaccount>, or an ICRC-1 textual account (legacy account ids are not accepted)"
+    )]
+    pub to: String,
+
+    #[arg(
+        long,
+        required = true,
+        value_name = "KINIC",
+        help = "Amount of KINIC to send, e.g. 1.5 (the ledger fee is charged on top)"
+    )]
+    pub amount: String,
+
+    #[arg(long, help = "Memo stored with the transfer (UTF-8, at most 32 bytes)")]
+    pub memo: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "NANOS",
+        help = "Reuse the created_at_time printed by an earlier attempt so the ledger deduplicates a retry"
+    )]
+    pub created_at_time: Option<u64>,
+
+    #[arg(short, long, help = "Skip the confirmation prompt before sending")]
+    pub yes: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct AllowanceArgs {
+    #[command(subcommand)]
+    pub command: AllowanceCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AllowanceCommand {
+    #[command(about = "List allowances granted to known spenders (the launcher by default)")]
+    List(AllowanceListArgs),
+    #[command(about = "Show the allowance granted to a specific spender")]
+    Show(AllowanceShowArgs),
+    #[command(about = "Revoke an allowance by approving zero tokens")]
+    Revoke(AllowanceRevokeArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct AllowanceListArgs {
+    #[arg(
+        long = "spender",
+        value_name = "ACCOUNT",
+        help = "Additional spender account to include (repeatable)"
+    )]
+    pub spenders: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct AllowanceShowArgs {
+    #[arg(
+        long,
+        required = true,
+        value_name = "ACCOUNT",
+        help = "Spender principal or ICRC-1 account"
+    )]
+    pub spender: String,
+}
+
+#[derive(Args, Debug)]
+pub struct AllowanceRevokeArgs {
+    #[arg(
+        long,
+        value_name = "ACCOUNT",
+        help = "Spender principal or ICRC-1 account (default: the launcher canister)"
+    )]
+    pub spender: Option<String>,
+
+    #[arg(short, long, help = "Skip the confirmation prompt before revoking")]
+    pub yes: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct HistoryArgs {
+    #[arg(
+        long,
+        default_value_t = 20,
+        value_name = "N",
+        help = "Maximum number of transactions to show (newest first)"
+    )]
+    pub limit: usize,
+
+    #[arg(
+        long,
+        default_value_t = 10_000,
+        value_name = "N",
+        help = "Maximum number of recent ledger blocks to scan"
+    )]
+    pub max_blocks: u64,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Also write the transactions to a CSV file"
+    )]
+    pub csv: Option<PathBuf>,
+}
 
 #[derive(Args, Debug)]
 pub struct AskAiArgs {
@@ -283,4 +615,122 @@ pub struct AskAiArgs {
 }
 
 #[derive(Args, Debug)]
-pub struct LoginArgs {}
+pub struct LoginArgs {
+    #[arg(
+        long,
+        value_name = "HOURS",
+        default_value_t = 6,
+        help = "Requested delegation lifetime in hours (max 720)"
+    )]
+    pub ttl: u64,
+
+    #[arg(
+        long,
+        default_value_t = 8620,
+        help = "Local callback port; 0 picks a free port. The port is part of the derivation origin, so changing it changes your principal"
+    )]
+    pub port: u16,
+
+    #[arg(
+        long,
+        value_name = "URL",
+        default_value = "https://id.ai/#authorize",
+        help = "Identity provider authorize URL (e.g. a local II canister: http://<canister-id>.localhost:4943/#authorize)"
+    )]
+    pub provider_url: String,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value_t = 300,
+        help = "How long to wait for the browser callback"
+    )]
+    pub timeout: u64,
+
+    #[arg(
+        long,
+        help = "Do not launch a browser; print the login URL, save the page for serving elsewhere, and accept a pasted delegation blob"
+    )]
+    pub no_browser: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = SessionProtection::None,
+        help = "How to protect the session key at rest"
+    )]
+    pub protect: SessionProtection,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum SessionProtection {
+    /// Plaintext hex in the identity file (0600 permissions)
+    None,
+    /// Stored in the OS keyring
+    Keyring,
+    /// Encrypted with a passphrase (Argon2id + AES-256-GCM); reads KINIC_SESSION_PASSPHRASE if set
+    Passphrase,
+}
+
+#[derive(Args, Debug)]
+pub struct WhoamiArgs {}
+
+#[derive(Args, Debug)]
+pub struct LogoutArgs {
+    #[arg(long, help = "Remove every stored session, including the default one")]
+    pub all: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct SessionsArgs {
+    #[command(subcommand)]
+    pub command: SessionsCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SessionsCommand {
+    #[command(about = "List stored sessions with their principal and expiry")]
+    List,
+    #[command(about = "Change how the selected session's key is protected at rest")]
+    Protect(SessionsProtectArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct SessionsProtectArgs {
+    #[arg(long, value_enum, help = "New protection for the session key")]
+    pub mode: SessionProtection,
+}
+
+#[derive(Args, Debug)]
+pub struct ServeArgs {
+    #[arg(
+        long,
+        default_value = "127.0.0.1:8080",
+        value_name = "ADDR",
+        help = "Address to listen on"
+    )]
+    pub bind: std::net::SocketAddr,
+
+    #[arg(
+        long,
+        value_name = "TOKEN",
+        help = "Require `Authorization: Bearer <TOKEN>` on every request; reads KINIC_SERVE_TOKEN if unset"
+    )]
+    pub token: Option<String>,
+
+    #[arg(
+        long,
+        help = "Allow a non-loopback --bind without a token, exposing this identity to anyone who can reach it"
+    )]
+    pub insecure_no_auth: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct McpArgs {
+    #[arg(
+        long,
+        value_name = "PRINCIPAL",
+        help = "Default memory canister for tools that are called without a memory_id"
+    )]
+    pub memory_id: Option<String>,
+}
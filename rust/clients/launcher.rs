@@ -4,7 +4,10 @@ use anyhow::{Context, Result};
 use candid::{CandidType, Decode, Deserialize, Nat};
 use ic_agent::{Agent, export::Principal};
 use icrc_ledger_types::{
-    icrc1::{account::Account, transfer::TransferError},
+    icrc1::{
+        account::{Account, Subaccount},
+        transfer::TransferError,
+    },
     icrc2::{
         approve::{ApproveArgs, ApproveError},
         transfer_from::TransferFromError,
@@ -13,10 +16,13 @@ use icrc_ledger_types::{
 use serde_json::json;
 use thiserror::Error;
 
-use crate::clients::{LAUNCHER_CANISTER, LEDGER_CANISTER};
+use crate::{
+    clients::{LAUNCHER_CANISTER, LEDGER_CANISTER},
+    error::KinicError,
+};
 
 const DEFAULT_VECTOR_DIM: u64 = 1024;
-const APPROVAL_TTL_NS: u64 = 10 * 60 * 1_000_000_000;
+pub const APPROVAL_TTL_NS: u64 = 10 * 60 * 1_000_000_000;
 
 pub struct LauncherClient {
     agent: Agent,
@@ -49,11 +55,18 @@ impl LauncherClient {
         Ok(price)
     }
 
-    pub async fn approve_launcher(&self, amount: &Nat) -> Result<()> {
+    /// Approves the launcher to pull `amount`. `fee` must be the ledger's current `icrc1_fee`,
+    /// or the ledger rejects the call with `BadFee`.
+    pub async fn approve_launcher(
+        &self,
+        amount: &Nat,
+        fee: &Nat,
+        from_subaccount: Option<Subaccount>,
+    ) -> Result<()> {
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64;
 
         let args = ApproveArgs {
-            from_subaccount: None,
+            from_subaccount,
             spender: Account {
                 owner: self.launcher_id,
                 subaccount: None,
@@ -61,7 +74,7 @@ impl LauncherClient {
             amount: amount.clone(),
             expected_allowance: None,
             expires_at: Some(now + APPROVAL_TTL_NS),
-            fee: Some(Nat::from(100_000u64)),
+            fee: Some(fee.clone()),
             memo: None,
             created_at_time: Some(now),
         };
@@ -77,7 +90,7 @@ impl LauncherClient {
 
         Decode!(&response, std::result::Result<Nat, ApproveError>)
             .context("Failed to decode icrc2_approve response")?
-            .map_err(anyhow::Error::msg)?;
+            .map_err(|e| KinicError::Ledger(format!("Failed to approve the launcher: {e}")))?;
         Ok(())
     }
 
@@ -109,6 +122,13 @@ impl LauncherClient {
         Ok(result)
     }
 
+    pub async fn instance_state(&self, instance: &Principal) -> Result<Option<State>> {
+        let states = self.list_memories().await?;
+        Ok(states
+            .into_iter()
+            .find(|state| state.principal() == Some(instance)))
+    }
+
     pub async fn update_instance(&self, instance_pid_str: &str) -> Result<()> {
         let payload = encode_update_instance_args(instance_pid_str)?;
         let response = self
@@ -184,3 +204,50 @@ pub enum State {
     SettingUp(Principal),
     Running(Principal),
 }
+
+impl State {
+    pub fn label(&self) -> &'static str {
+        match self {
+            State::Empty(_) => "Empty",
+            State::Pending(_) => "Pending",
+            State::Creation(_) => "Creation",
+            State::Installation(_, _) => "Installation",
+            State::SettingUp(_) => "SettingUp",
+            State::Running(_) => "Running",
+        }
+    }
+
+    pub fn principal(&self) -> Option<&Principal> {
+        match self {
+            State::Installation(principal, _)
+            | State::SettingUp(principal)
+            | State::Running(principal) => Some(principal),
+            _ => None,
+        }
+    }
+
+    /// Raw payload attached by the launcher: either the JSON deploy args or a status message.
+    pub fn detail(&self) -> Option<&str> {
+        match self {
+            State::Empty(text)
+            | State::Pending(text)
+            | State::Creation(text)
+            | State::Installation(_, text) => Some(text),
+            State::SettingUp(_) | State::Running(_) => None,
+        }
+    }
+
+    /// Name and description parsed from the deploy args, when the launcher still carries them.
+    pub fn deploy_metadata(&self) -> Option<DeployMetadata> {
+        self.detail()
+            .and_then(|text| serde_json::from_str::<DeployMetadata>(text).ok())
+    }
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+pub struct DeployMetadata {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+}
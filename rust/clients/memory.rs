@@ -1,18 +1,40 @@
-use anyhow::{Context, Result};
-use candid::Decode;
+use std::{
+    collections::HashMap,
+    fmt,
+    str::FromStr,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use anyhow::{Context, Result, bail};
+use candid::{CandidType, Decode, Deserialize, Nat};
 use ic_agent::{Agent, export::Principal};
+use tracing::warn;
+
+use crate::error::{KinicError, is_method_not_found};
 
+/// Client for one memory canister. The calls follow `candid/memory.did`.
 pub struct MemoryClient {
     agent: Agent,
     canister_id: Principal,
+    dims: Arc<KnownDims>,
 }
 
 impl MemoryClient {
     pub fn new(agent: Agent, canister_id: Principal) -> Self {
-        Self { agent, canister_id }
+        Self::with_dims(agent, canister_id, Arc::default())
+    }
+
+    /// Shares `dims` with other clients, so a dimension is looked up once per memory.
+    pub fn with_dims(agent: Agent, canister_id: Principal, dims: Arc<KnownDims>) -> Self {
+        Self {
+            agent,
+            canister_id,
+            dims,
+        }
     }
 
     pub async fn insert(&self, embedding: Vec<f32>, text: &str) -> Result<()> {
+        self.ensure_dimension(embedding.len()).await?;
         let payload = encode_insert_args(embedding, text)?;
         let response = self
             .agent
@@ -27,6 +49,7 @@ impl MemoryClient {
     }
 
     pub async fn search(&self, embedding: Vec<f32>) -> Result<Vec<(f32, String)>> {
+        self.ensure_dimension(embedding.len()).await?;
         let payload = encode_search_args(embedding)?;
         let response = self
             .agent
@@ -56,6 +79,7 @@ impl MemoryClient {
         Ok(results)
     }
 
+    /// Grants `role` to `principal`, replacing any role the user already has.
     pub async fn add_new_user(&self, principal: Principal, role: u8) -> Result<()> {
         let payload = encode_add_user_args(principal, role)?;
         self.agent
@@ -68,6 +92,85 @@ impl MemoryClient {
         Ok(())
     }
 
+    /// Lists every user with access to the memory as `(principal, role code)`.
+    pub async fn get_users(&self) -> Result<Vec<(Principal, u8)>> {
+        let response = self
+            .agent
+            .query(&self.canister_id, "get_users")
+            .with_arg(candid::encode_args(())?)
+            .call()
+            .await
+            .context("Failed to call get_users on memory canister")?;
+
+        let users = Decode!(&response, Vec<(Principal, u8)>)
+            .context("Failed to decode get_users response")?;
+        Ok(users)
+    }
+
+    pub async fn remove_user(&self, principal: Principal) -> Result<()> {
+        let payload = candid::encode_one(principal)?;
+        self.agent
+            .update(&self.canister_id, "remove_user")
+            .with_arg(payload)
+            .call_and_wait()
+            .await
+            .context("Failed to call remove_user on memory canister")?;
+
+        Ok(())
+    }
+
+    /// Returns every stored record as `(embedding, text)`. The canister has no paged variant, so
+    /// the whole memory must fit in a single query reply (about 3 MB).
+    pub async fn export(&self) -> Result<Vec<(Vec<f32>, String)>> {
+        let response = self
+            .agent
+            .query(&self.canister_id, "export")
+            .with_arg(candid::encode_args(())?)
+            .call()
+            .await
+            .context("Failed to call export on memory canister")?;
+
+        let records = Decode!(&response, Vec<(Vec<f32>, String)>)
+            .context("Failed to decode export response")?;
+        Ok(records)
+    }
+
+    /// Fails early when `len` differs from the memory's embedding dimension. The dimension comes
+    /// from [`KnownDims`], and is re-read from the canister on a miss or mismatch in case the
+    /// memory was reset elsewhere. A canister that reports no dimension, or whose metadata
+    /// cannot be read, is remembered as unchecked and not asked again by this client.
+    pub async fn ensure_dimension(&self, len: usize) -> Result<()> {
+        match self.dims.lookup(&self.canister_id) {
+            Some(Some(dim)) if dim == len => return Ok(()),
+            Some(None) => return Ok(()),
+            _ => {}
+        }
+        let dim = match self.metadata().await {
+            Ok(metadata) => metadata.dim.map(|dim| dim as usize),
+            Err(err) if is_method_not_found(&err) => None,
+            Err(err) => {
+                warn!(
+                    "skipping dimension checks for {}: {err:#}",
+                    self.canister_id
+                );
+                None
+            }
+        };
+        self.dims.record(self.canister_id, dim);
+        match dim {
+            Some(dim) if dim != len => bail!(KinicError::Validation(format!(
+                "Embedding has {len} dimensions but memory {} expects {dim}. Use a matching embedding model, or `reset --dim {len}` the memory.",
+                self.canister_id
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Dimension this client knows for its memory, without querying the canister.
+    pub fn known_dim(&self) -> Option<usize> {
+        self.dims.lookup(&self.canister_id).flatten()
+    }
+
     pub async fn reset(&self, dim: usize) -> Result<()> {
         let payload = encode_reset_args(dim)?;
         let response = self
@@ -79,14 +182,117 @@ impl MemoryClient {
             .context("Failed to call reset on memory canister")?;
 
         Decode!(&response, ()).context("Failed to decode reset response")?;
+        self.dims.record(self.canister_id, Some(dim));
         Ok(())
     }
 
+    pub async fn metadata(&self) -> Result<MemoryMetadata> {
+        let response = self
+            .agent
+            .query(&self.canister_id, "get_metadata")
+            .call()
+            .await
+            .context("Failed to call get_metadata on memory canister")?;
+
+        let metadata =
+            Decode!(&response, MemoryMetadata).context("Failed to decode get_metadata response")?;
+        Ok(metadata)
+    }
+
     pub fn canister_id(&self) -> &Principal {
         &self.canister_id
     }
 }
 
+/// Embedding dimensions learned per memory canister. `None` marks a memory that reported no
+/// dimension, so its inserts are not checked.
+#[derive(Default)]
+pub struct KnownDims(Mutex<HashMap<Principal, Option<usize>>>);
+
+impl KnownDims {
+    /// `None` when the memory has not been looked up yet.
+    pub fn lookup(&self, memory: &Principal) -> Option<Option<usize>> {
+        self.entries().get(memory).copied()
+    }
+
+    pub fn record(&self, memory: Principal, dim: Option<usize>) {
+        self.entries().insert(memory, dim);
+    }
+
+    fn entries(&self) -> MutexGuard<'_, HashMap<Principal, Option<usize>>> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Access level granted to a user of a memory canister.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Admin,
+    Writer,
+    Reader,
+}
+
+impl Role {
+    /// Maps the numeric code used by the canister's `get_users` back to a role.
+    pub fn from_code(code: u8) -> Result<Self> {
+        match code {
+            1 => Ok(Self::Admin),
+            2 => Ok(Self::Writer),
+            3 => Ok(Self::Reader),
+            _ => bail!("memory canister reported unknown role code {code}"),
+        }
+    }
+
+    /// Numeric code expected by the canister's `add_new_user`.
+    pub fn code(&self) -> u8 {
+        match self {
+            Role::Admin => 1,
+            Role::Writer => 2,
+            Role::Reader => 3,
+        }
+    }
+}
+
+/// Parses `admin`, `writer` or `reader` (case-insensitive).
+impl FromStr for Role {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "admin" => Ok(Self::Admin),
+            "writer" => Ok(Self::Writer),
+            "reader" => Ok(Self::Reader),
+            _ => bail!(KinicError::Validation(
+                "role must be one of: admin, writer, reader".to_string()
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Role::Admin => "admin",
+            Role::Writer => "writer",
+            Role::Reader => "reader",
+        };
+        f.write_str(label)
+    }
+}
+
+/// Summary reported by a memory canister (`MemoryMetadata` in `candid/memory.did`); fields are
+/// optional so older canisters still decode.
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct MemoryMetadata {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub dim: Option<u64>,
+    pub count: Option<u64>,
+    pub cycles: Option<Nat>,
+}
+
 fn encode_insert_args(embedding: Vec<f32>, text: &str) -> Result<Vec<u8>> {
     Ok(candid::encode_args((embedding, text.to_string()))?)
 }
@@ -0,0 +1,481 @@
+use std::{collections::BTreeMap, fmt, fs};
+
+use anyhow::{Context, Result, anyhow, bail};
+use ic_agent::export::Principal;
+use serde::Deserialize;
+use tracing::info;
+
+use crate::{
+    cli::{AclArgs, AclCommand},
+    clients::memory::{MemoryClient, Role},
+    error::KinicError,
+    kinic_client::KinicClient,
+};
+
+use super::{CommandContext, prompt::confirm};
+
+pub async fn handle(args: AclArgs, ctx: &CommandContext) -> Result<()> {
+    match args.command {
+        AclCommand::List(list) => {
+            let (client, _) = build_memory_client(&list.memory_id, ctx).await?;
+            let users = fetch_users(&client).await?;
+            if users.is_empty() {
+                println!("No users found for memory {}", client.canister_id());
+                return Ok(());
+            }
+            println!("Users of memory {}:", client.canister_id());
+            for (user, role) in &users {
+                println!("- {} {role}", display_user(user));
+            }
+        }
+        AclCommand::Add(add) => {
+            let role = add.role.parse::<Role>()?;
+            let user = parse_user(&add.user, role)?;
+            let (client, _) = build_memory_client(&add.memory_id, ctx).await?;
+            if let Some(current) = fetch_users(&client).await?.get(&user) {
+                bail!(KinicError::Validation(format!(
+                    "{} already has role {current}; use `acl set-role` to change it",
+                    display_user(&user)
+                )));
+            }
+            grant(&client, user, role).await?;
+            println!("Granted {role} to {}", display_user(&user));
+        }
+        AclCommand::SetRole(set) => {
+            let role = set.target.role.parse::<Role>()?;
+            let user = parse_user(&set.target.user, role)?;
+            let (client, caller) = build_memory_client(&set.target.memory_id, ctx).await?;
+            let users = fetch_users(&client).await?;
+            let Some(current) = users.get(&user).copied() else {
+                bail!(KinicError::Validation(format!(
+                    "{} has no access yet; use `acl add` to grant a role",
+                    display_user(&user)
+                )));
+            };
+            if current == role {
+                println!("{} already has role {role}", display_user(&user));
+                return Ok(());
+            }
+            let change = Change::Update {
+                user,
+                from: current,
+                to: role,
+            };
+            check_lockout(&users, &[change], &caller, set.allow_self_demotion)?;
+            // There is no set-role method: add_new_user overwrites an existing user's role.
+            grant(&client, user, role).await?;
+            println!("Changed {} from {current} to {role}", display_user(&user));
+        }
+        AclCommand::Remove(remove) => {
+            let user = parse_principal(&remove.user)?;
+            let (client, caller) = build_memory_client(&remove.memory_id, ctx).await?;
+            let users = fetch_users(&client).await?;
+            let Some(current) = users.get(&user).copied() else {
+                println!("{} has no access; nothing to remove", display_user(&user));
+                return Ok(());
+            };
+            let change = Change::Remove {
+                user,
+                role: current,
+            };
+            check_lockout(&users, &[change], &caller, remove.allow_self_demotion)?;
+            let question = format!("Remove {} ({current})?", display_user(&user));
+            if !confirm(&question, remove.yes)? {
+                println!("Aborted; access left unchanged.");
+                return Ok(());
+            }
+            revoke(&client, user).await?;
+            println!("Removed {}", display_user(&user));
+        }
+        AclCommand::Apply(apply) => {
+            let desired = load_policy(&apply.file)?;
+            let (client, caller) = build_memory_client(&apply.memory_id, ctx).await?;
+            let current = fetch_users(&client).await?;
+            let changes = diff(&current, &desired, apply.prune, &caller);
+            if changes.is_empty() {
+                println!(
+                    "Memory {} already matches the policy.",
+                    client.canister_id()
+                );
+                return Ok(());
+            }
+
+            println!("Planned changes for memory {}:", client.canister_id());
+            for change in &changes {
+                println!("  {change}");
+            }
+            let unlisted = current
+                .keys()
+                .filter(|user| !desired.contains_key(*user))
+                .count();
+            if !apply.prune && unlisted > 0 {
+                println!(
+                    "  ({unlisted} user(s) not in the policy are kept; pass --prune to remove them)"
+                );
+            }
+            check_lockout(&current, &changes, &caller, apply.allow_self_demotion)?;
+            if !confirm("Apply these changes?", apply.yes)? {
+                println!("Aborted; no changes applied.");
+                return Ok(());
+            }
+
+            for change in &changes {
+                match *change {
+                    Change::Add { user, role } | Change::Update { user, to: role, .. } => {
+                        grant(&client, user, role).await?
+                    }
+                    Change::Remove { user, .. } => revoke(&client, user).await?,
+                }
+            }
+            println!("Applied {} change(s).", changes.len());
+        }
+    }
+    Ok(())
+}
+
+/// Parses a user for a role grant, refusing to make `anonymous` an admin.
+pub(crate) fn parse_user(user_id: &str, role: Role) -> Result<Principal> {
+    let user = parse_principal(user_id)?;
+    if role == Role::Admin && user == Principal::anonymous() {
+        bail!(KinicError::Validation(
+            "cannot grant admin role to anonymous".to_string()
+        ));
+    }
+    Ok(user)
+}
+
+fn parse_principal(user_id: &str) -> Result<Principal> {
+    if user_id == "anonymous" {
+        Ok(Principal::anonymous())
+    } else {
+        Principal::from_text(user_id).with_context(|| format!("invalid principal text: {user_id}"))
+    }
+}
+
+fn display_user(user: &Principal) -> String {
+    if *user == Principal::anonymous() {
+        "anonymous".to_string()
+    } else {
+        user.to_text()
+    }
+}
+
+/// Policy file layout: `users:` mapping each principal (or `anonymous`) to a role.
+#[derive(Deserialize)]
+struct Policy {
+    #[serde(default)]
+    users: BTreeMap<String, String>,
+}
+
+fn load_policy(path: &std::path::Path) -> Result<BTreeMap<Principal, Role>> {
+    let payload = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read policy file {}", path.display()))?;
+    let policy: Policy = serde_yaml::from_str(&payload)
+        .with_context(|| format!("Failed to parse policy file {}", path.display()))?;
+    policy
+        .users
+        .iter()
+        .map(|(user_id, role)| {
+            let role = role
+                .parse::<Role>()
+                .with_context(|| format!("Invalid role for {user_id}"))?;
+            Ok((parse_user(user_id, role)?, role))
+        })
+        .collect()
+}
+
+enum Change {
+    Add {
+        user: Principal,
+        role: Role,
+    },
+    Update {
+        user: Principal,
+        from: Role,
+        to: Role,
+    },
+    Remove {
+        user: Principal,
+        role: Role,
+    },
+}
+
+impl Change {
+    fn user(&self) -> &Principal {
+        match self {
+            Change::Add { user, .. }
+            | Change::Update { user, .. }
+            | Change::Remove { user, .. } => user,
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Add { user, role } => write!(f, "+ {} {role}", display_user(user)),
+            Change::Update { user, from, to } => {
+                write!(f, "~ {} {from} -> {to}", display_user(user))
+            }
+            Change::Remove { user, role } => write!(f, "- {} {role}", display_user(user)),
+        }
+    }
+}
+
+/// Changes that turn `current` into `desired`. The caller's own change comes last, so a
+/// demotion cannot cut off the rest of the batch half-way through.
+fn diff(
+    current: &BTreeMap<Principal, Role>,
+    desired: &BTreeMap<Principal, Role>,
+    prune: bool,
+    caller: &Principal,
+) -> Vec<Change> {
+    let mut changes = Vec::new();
+    for (user, role) in desired {
+        match current.get(user) {
+            None => changes.push(Change::Add {
+                user: *user,
+                role: *role,
+            }),
+            Some(from) if from != role => changes.push(Change::Update {
+                user: *user,
+                from: *from,
+                to: *role,
+            }),
+            Some(_) => {}
+        }
+    }
+    if prune {
+        for (user, role) in current {
+            if !desired.contains_key(user) {
+                changes.push(Change::Remove {
+                    user: *user,
+                    role: *role,
+                });
+            }
+        }
+    }
+    changes.sort_by_key(|change| change.user() == caller);
+    changes
+}
+
+/// Refuses changes that remove or demote the caller, or that leave the memory without an
+/// admin, unless `allow` is set.
+fn check_lockout(
+    current: &BTreeMap<Principal, Role>,
+    changes: &[Change],
+    caller: &Principal,
+    allow: bool,
+) -> Result<()> {
+    if allow {
+        return Ok(());
+    }
+    let mut result = current.clone();
+    for change in changes {
+        match *change {
+            Change::Add { user, role } | Change::Update { user, to: role, .. } => {
+                result.insert(user, role);
+            }
+            Change::Remove { user, .. } => {
+                result.remove(&user);
+            }
+        }
+    }
+
+    let problem = match (current.get(caller), result.get(caller)) {
+        (Some(before), None) => Some(format!("removes your own {before} access")),
+        (Some(before), Some(after)) if privilege(*after) < privilege(*before) => {
+            Some(format!("demotes you from {before} to {after}"))
+        }
+        _ => None,
+    };
+    let admins = |users: &BTreeMap<Principal, Role>| {
+        users.values().filter(|role| **role == Role::Admin).count()
+    };
+    let problem = problem.or_else(|| {
+        (admins(current) > 0 && admins(&result) == 0)
+            .then(|| "leaves the memory without an admin".to_string())
+    });
+    match problem {
+        Some(problem) => bail!(KinicError::Validation(format!(
+            "This change {problem} ({}); pass --allow-self-demotion to apply it anyway",
+            display_user(caller)
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn privilege(role: Role) -> u8 {
+    match role {
+        Role::Admin => 3,
+        Role::Writer => 2,
+        Role::Reader => 1,
+    }
+}
+
+async fn fetch_users(client: &MemoryClient) -> Result<BTreeMap<Principal, Role>> {
+    client
+        .get_users()
+        .await?
+        .into_iter()
+        .map(|(user, code)| Ok((user, Role::from_code(code)?)))
+        .collect()
+}
+
+async fn grant(client: &MemoryClient, user: Principal, role: Role) -> Result<()> {
+    client
+        .add_new_user(user, role.code())
+        .await
+        .context("Failed to grant role on memory canister")?;
+    info!(canister_id = %client.canister_id(), %user, %role, "granted role");
+    Ok(())
+}
+
+async fn revoke(client: &MemoryClient, user: Principal) -> Result<()> {
+    client
+        .remove_user(user)
+        .await
+        .context("Failed to remove user from memory canister")?;
+    info!(canister_id = %client.canister_id(), %user, "removed user");
+    Ok(())
+}
+
+/// Returns a client for the memory and the caller's principal.
+async fn build_memory_client(id: &str, ctx: &CommandContext) -> Result<(MemoryClient, Principal)> {
+    let client = KinicClient::connect(&ctx.agent_factory).await?;
+    let memory = Principal::from_text(id).context("Failed to parse canister id for acl command")?;
+    let caller = client
+        .agent()
+        .get_principal()
+        .map_err(|e| anyhow!("Failed to derive principal for current identity: {e}"))?;
+    Ok((client.memory(memory), caller))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(n: u8) -> Principal {
+        Principal::from_slice(&[n; 29])
+    }
+
+    fn lines(changes: &[Change]) -> Vec<String> {
+        changes.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn diff_adds_and_updates_without_prune() {
+        let current = BTreeMap::from([(user(1), Role::Reader), (user(2), Role::Writer)]);
+        let desired = BTreeMap::from([(user(1), Role::Writer), (user(3), Role::Admin)]);
+        let changes = diff(&current, &desired, false, &user(9));
+        assert_eq!(
+            lines(&changes),
+            vec![
+                format!("~ {} reader -> writer", user(1)),
+                format!("+ {} admin", user(3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_removes_unlisted_users_with_prune() {
+        let current = BTreeMap::from([
+            (user(1), Role::Reader),
+            (Principal::anonymous(), Role::Reader),
+        ]);
+        let desired = BTreeMap::from([(user(1), Role::Reader)]);
+        assert!(diff(&current, &desired, false, &user(9)).is_empty());
+        assert_eq!(
+            lines(&diff(&current, &desired, true, &user(9))),
+            vec!["- anonymous reader".to_string()]
+        );
+    }
+
+    #[test]
+    fn diff_is_empty_when_policy_matches() {
+        let current = BTreeMap::from([(user(1), Role::Admin)]);
+        assert!(diff(&current, &current, true, &user(9)).is_empty());
+    }
+
+    #[test]
+    fn diff_puts_the_callers_change_last() {
+        let current = BTreeMap::from([(user(1), Role::Admin), (user(5), Role::Reader)]);
+        let desired = BTreeMap::from([
+            (user(1), Role::Writer),
+            (user(2), Role::Admin),
+            (user(5), Role::Writer),
+        ]);
+        let changes = diff(&current, &desired, false, &user(1));
+        assert_eq!(
+            lines(&changes),
+            vec![
+                format!("+ {} admin", user(2)),
+                format!("~ {} reader -> writer", user(5)),
+                format!("~ {} admin -> writer", user(1)),
+            ]
+        );
+        assert!(check_lockout(&current, &changes, &user(1), false).is_err());
+        assert!(check_lockout(&current, &changes, &user(1), true).is_ok());
+    }
+
+    #[test]
+    fn lockout_refuses_pruning_the_caller() {
+        let current = BTreeMap::from([(user(1), Role::Admin), (user(2), Role::Admin)]);
+        let desired = BTreeMap::from([(user(2), Role::Admin)]);
+        let changes = diff(&current, &desired, true, &user(1));
+        let err = check_lockout(&current, &changes, &user(1), false).unwrap_err();
+        assert!(err.to_string().contains("removes your own admin access"));
+        assert!(err.to_string().contains("--allow-self-demotion"));
+    }
+
+    #[test]
+    fn lockout_refuses_leaving_no_admin() {
+        let current = BTreeMap::from([(user(1), Role::Writer), (user(2), Role::Admin)]);
+        let desired = BTreeMap::from([(user(1), Role::Writer), (user(2), Role::Reader)]);
+        let changes = diff(&current, &desired, false, &user(1));
+        let err = check_lockout(&current, &changes, &user(1), false).unwrap_err();
+        assert!(err.to_string().contains("without an admin"));
+    }
+
+    #[test]
+    fn lockout_allows_changes_that_keep_the_caller_and_an_admin() {
+        let current = BTreeMap::from([(user(1), Role::Admin), (user(2), Role::Admin)]);
+        let desired = BTreeMap::from([(user(1), Role::Admin), (user(3), Role::Reader)]);
+        let changes = diff(&current, &desired, true, &user(1));
+        assert!(check_lockout(&current, &changes, &user(1), false).is_ok());
+
+        let promote = [Change::Update {
+            user: user(1),
+            from: Role::Writer,
+            to: Role::Admin,
+        }];
+        let current = BTreeMap::from([(user(1), Role::Writer), (user(2), Role::Admin)]);
+        assert!(check_lockout(&current, &promote, &user(1), false).is_ok());
+    }
+
+    #[test]
+    fn load_policy_parses_users_and_rejects_anonymous_admin() {
+        let dir = std::env::temp_dir().join(format!("kinic-acl-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let ok = dir.join("ok.yaml");
+        fs::write(
+            &ok,
+            format!("users:\n  {}: writer\n  anonymous: reader\n", user(4)),
+        )
+        .unwrap();
+        let policy = load_policy(&ok).unwrap();
+        assert_eq!(policy.get(&user(4)), Some(&Role::Writer));
+        assert_eq!(policy.get(&Principal::anonymous()), Some(&Role::Reader));
+
+        let bad = dir.join("bad.yaml");
+        fs::write(&bad, "users:\n  anonymous: admin\n").unwrap();
+        assert!(load_policy(&bad).is_err());
+
+        let unknown = dir.join("unknown.yaml");
+        fs::write(&unknown, format!("users:\n  {}: owner\n", user(4))).unwrap();
+        assert!(load_policy(&unknown).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,150 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, anyhow};
+use ic_agent::{Agent, export::Principal};
+use icrc_ledger_types::{
+    icrc1::account::{Account, Subaccount},
+    icrc2::allowance::Allowance,
+};
+use tracing::info;
+
+use crate::{
+    cli::{AllowanceArgs, AllowanceCommand},
+    clients::LAUNCHER_CANISTER,
+    ledger::{fetch_allowance, format_kinic, nat_to_u128, parse_account, revoke_allowance},
+};
+
+use super::{CommandContext, format_remaining, prompt::confirm};
+
+pub async fn handle(args: AllowanceArgs, ctx: &CommandContext) -> Result<()> {
+    let agent = ctx.agent_factory.build().await?;
+    let owner = owner_account(&agent, ctx.subaccount)?;
+
+    match args.command {
+        AllowanceCommand::List(list) => {
+            let mut spenders = vec![launcher_account()?];
+            for spender in &list.spenders {
+                spenders.push(parse_account(spender)?);
+            }
+            println!("Allowances granted by {owner}:");
+            for spender in spenders {
+                let allowance = fetch_allowance(&agent, owner, spender).await?;
+                println!("- {spender}: {}", describe_allowance(&allowance)?);
+            }
+        }
+        AllowanceCommand::Show(show) => {
+            let spender = parse_account(&show.spender)?;
+            let allowance = fetch_allowance(&agent, owner, spender).await?;
+            println!("Owner:     {owner}");
+            println!("Spender:   {spender}");
+            println!("Allowance: {}", describe_allowance(&allowance)?);
+        }
+        AllowanceCommand::Revoke(revoke) => {
+            let spender = match &revoke.spender {
+                Some(text) => parse_account(text)?,
+                None => launcher_account()?,
+            };
+            let allowance = fetch_allowance(&agent, owner, spender).await?;
+            if allowance.allowance == 0u64 {
+                println!("No active allowance for {spender}; nothing to revoke.");
+                return Ok(());
+            }
+            println!(
+                "Current allowance for {spender}: {}",
+                describe_allowance(&allowance)?
+            );
+            if !confirm("Revoke it (costs one ledger fee)?", revoke.yes)? {
+                println!("Aborted; allowance left unchanged.");
+                return Ok(());
+            }
+            let block = revoke_allowance(&agent, owner.subaccount, spender).await?;
+            info!(%spender, %block, "allowance revoked");
+            println!("Revoked allowance for {spender} in block {block}");
+        }
+    }
+
+    Ok(())
+}
+
+/// What to do with an allowance the launcher still holds after a failed deployment. Revoking
+/// costs a ledger fee, so it only happens without asking when the user requested it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RevokeOnFailure {
+    Ask,
+    Always,
+    Never,
+}
+
+/// Reports any allowance the launcher still holds after a failed deployment and revokes it
+/// according to `policy`. Errors are reported rather than returned so the original failure
+/// stays visible.
+pub async fn offer_launcher_revoke(
+    agent: &Agent,
+    subaccount: Option<Subaccount>,
+    policy: RevokeOnFailure,
+) {
+    if let Err(err) = try_offer_launcher_revoke(agent, subaccount, policy).await {
+        eprintln!("Could not check the leftover launcher allowance: {err:#}");
+    }
+}
+
+async fn try_offer_launcher_revoke(
+    agent: &Agent,
+    subaccount: Option<Subaccount>,
+    policy: RevokeOnFailure,
+) -> Result<()> {
+    let owner = owner_account(agent, subaccount)?;
+    let spender = launcher_account()?;
+    let allowance = fetch_allowance(agent, owner, spender).await?;
+    if allowance.allowance == 0u64 {
+        return Ok(());
+    }
+
+    eprintln!(
+        "Deployment failed, but the launcher still holds an allowance: {}",
+        describe_allowance(&allowance)?
+    );
+    let revoke = match policy {
+        RevokeOnFailure::Always => true,
+        RevokeOnFailure::Never => false,
+        RevokeOnFailure::Ask => {
+            confirm("Revoke the leftover allowance now?", false).unwrap_or(false)
+        }
+    };
+    if revoke {
+        let block = revoke_allowance(agent, owner.subaccount, spender).await?;
+        eprintln!("Revoked launcher allowance in block {block}");
+    } else {
+        eprintln!("Run `kinic-cli allowance revoke` to revoke it later.");
+    }
+    Ok(())
+}
+
+fn owner_account(agent: &Agent, subaccount: Option<Subaccount>) -> Result<Account> {
+    let owner = agent
+        .get_principal()
+        .map_err(|e| anyhow!("Failed to derive principal for current identity: {e}"))?;
+    Ok(Account { owner, subaccount })
+}
+
+fn launcher_account() -> Result<Account> {
+    let launcher =
+        Principal::from_text(LAUNCHER_CANISTER).context("Failed to parse launcher canister id")?;
+    Ok(Account::from(launcher))
+}
+
+fn describe_allowance(allowance: &Allowance) -> Result<String> {
+    let amount = format_kinic(nat_to_u128(&allowance.allowance)?);
+    let expiry = match allowance.expires_at {
+        None => "no expiry".to_string(),
+        Some(expires_at) => {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64;
+            if expires_at <= now {
+                "expired".to_string()
+            } else {
+                format!("expires in {}", format_remaining(expires_at - now))
+            }
+        }
+    };
+    Ok(format!("{amount} KINIC ({expiry})"))
+}
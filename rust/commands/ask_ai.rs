@@ -1,18 +1,16 @@
-use std::cmp::Ordering;
-
 use anyhow::{Context, Result};
 use ic_agent::export::Principal;
 use reqwest::Client;
 use tracing::info;
 
 use crate::{
-    agent::AgentFactory,
     cli::AskAiArgs,
     clients::memory::MemoryClient,
-    embedding::{embedding_base_url, fetch_embedding},
+    embedding::{embedding_base_url, embedding_error, fetch_embedding},
+    kinic_client::{KinicClient, sort_by_score},
 };
 
-use super::CommandContext;
+use super::{CommandContext, dim_cache};
 
 const MAX_QUERY_LEN: usize = 150;
 const MAX_RESULTS: usize = 5;
@@ -21,17 +19,24 @@ const MAX_HIT_LEN: usize = 600;
 const MAX_FULL_LEN: usize = 4096;
 const CHAT_PATH: &str = "/chat";
 
+/// Prompt built from the search results and the LLM's answer to it.
 pub struct AskAiResult {
     pub prompt: String,
     pub response: String,
+    /// Number of search results the memory returned.
     pub context_count: usize,
     pub top_k_used: usize,
+    /// The `top_k_used` best `(score, payload)` results, highest score first.
+    pub context: Vec<(f32, String)>,
 }
 
 pub async fn handle(args: AskAiArgs, ctx: &CommandContext) -> Result<()> {
     let memory = Principal::from_text(&args.memory_id)
         .context("Failed to parse canister id for ask-ai command")?;
-    let result = ask_ai_flow(&ctx.agent_factory, &memory, &args.query, args.top_k, "en").await?;
+    let client = KinicClient::connect(&ctx.agent_factory).await?;
+    dim_cache::seed(&client, &memory);
+    let result = client.ask(&memory, &args.query, args.top_k, "en").await?;
+    dim_cache::persist(&client, &memory);
 
     info!(
         canister_id = %memory,
@@ -55,30 +60,31 @@ pub async fn handle(args: AskAiArgs, ctx: &CommandContext) -> Result<()> {
     Ok(())
 }
 
-pub async fn ask_ai_flow(
-    agent_factory: &AgentFactory,
-    memory_id: &Principal,
+/// Searches the memory behind `client` and asks the LLM to answer `query` from the results.
+pub async fn ask_ai_with_client(
+    client: &MemoryClient,
     query: &str,
     top_k: usize,
     language: &str,
 ) -> Result<AskAiResult> {
-    let agent = agent_factory.build().await?;
-    let client = MemoryClient::new(agent, *memory_id);
-
     let embedding = fetch_embedding(query).await?;
     let mut results = client.search(embedding).await?;
 
-    results.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+    sort_by_score(&mut results);
 
     let limit = top_k.max(1);
     let prompt = build_prompt(query, &results, limit, language);
-    let llm_response = call_llm(&prompt).await?;
+    let llm_response = call_llm(&prompt).await.map_err(embedding_error)?;
+    let context_count = results.len();
+    let top_k_used = limit.min(context_count);
+    results.truncate(top_k_used);
 
     Ok(AskAiResult {
         prompt,
         response: llm_response,
-        context_count: results.len(),
-        top_k_used: limit.min(results.len()),
+        context_count,
+        top_k_used,
+        context: results,
     })
 }
 
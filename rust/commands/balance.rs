@@ -1,26 +1,58 @@
-use anyhow::{Result, anyhow};
+use anyhow::{Result, bail};
 use tracing::info;
 
-use crate::{cli::BalanceArgs, ledger::fetch_balance};
+use crate::{
+    cli::BalanceArgs,
+    config_store::{default_config_path, load_config},
+    error::KinicError,
+    kinic_client::KinicClient,
+    ledger::{format_kinic, kinic_f64, resolve_subaccount},
+};
 
 use super::CommandContext;
 
-pub async fn handle(_args: BalanceArgs, ctx: &CommandContext) -> Result<()> {
-    let agent = ctx.agent_factory.build().await?;
-    let principal = agent
-        .get_principal()
-        .map_err(|e| anyhow!("Failed to derive principal for current identity: {e}"))?;
+pub async fn handle(args: BalanceArgs, ctx: &CommandContext) -> Result<()> {
+    let client = KinicClient::connect(&ctx.agent_factory)
+        .await?
+        .with_subaccount(ctx.subaccount);
+    let principal = client.principal()?;
 
-    let balance = fetch_balance(&agent).await?;
-    let kinic = balance as f64 / 100_000_000f64;
+    if args.all_subaccounts {
+        if ctx.subaccount.is_some() {
+            bail!(KinicError::Validation(
+                "--all-subaccounts cannot be combined with --subaccount".to_string()
+            ));
+        }
+        let mut total: u128 = 0;
+        let default_balance = client.balance().await?;
+        total += default_balance;
+        println!("Balances for {principal}:");
+        println!("- (default): {} KINIC", format_kinic(default_balance));
+        let config = load_config(&default_config_path()?)?;
+        for name in config.subaccounts.keys() {
+            let subaccount = resolve_subaccount(name, &config.subaccounts)?;
+            let client = client.clone().with_subaccount(Some(subaccount));
+            let balance = client.balance().await?;
+            total += balance;
+            let account = client.account()?;
+            println!("- {name}: {} KINIC ({account})", format_kinic(balance));
+        }
+        info!(%principal, total_e8s = total, "fetched balances across subaccounts");
+        println!("Total: {} KINIC (= {total} e8s)", format_kinic(total));
+        return Ok(());
+    }
+
+    let account = client.account()?;
+    let balance = client.balance().await?;
+    let kinic = kinic_f64(balance);
 
     info!(
-        %principal,
+        %account,
         balance_base_units = balance,
         balance_kinic = kinic,
         "fetched token balance"
     );
-    println!("Balance for {principal}: {kinic:.7} KINIC (= {balance} e8s)");
+    println!("Balance for {account}: {kinic:.7} KINIC (= {balance} e8s)");
 
     Ok(())
 }
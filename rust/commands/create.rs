@@ -1,44 +1,184 @@
-use anyhow::{Result, bail};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
 use candid::Nat;
-use tracing::info;
+use ic_agent::export::Principal;
+use indicatif::{ProgressBar, ProgressStyle};
+use tracing::{debug, info};
 
 use crate::{
     cli::CreateArgs,
-    clients::launcher::LauncherClient,
-    ledger::fetch_balance,
+    clients::launcher::{APPROVAL_TTL_NS, State},
+    error::KinicError,
+    kinic_client::KinicClient,
+    ledger::{format_kinic, nat_to_u128},
 };
 
-use super::CommandContext;
+use super::{
+    CommandContext,
+    allowance::{RevokeOnFailure, offer_launcher_revoke},
+    prompt::confirm,
+};
 
-const TRANSFER_FEE_E8S: u128 = 100_000;
+const WAIT_POLL_INTERVAL: Duration = Duration::from_secs(3);
 
 pub async fn handle(args: CreateArgs, ctx: &CommandContext) -> Result<()> {
-    let agent = ctx.agent_factory.build().await?;
-    let balance = fetch_balance(&agent).await?;
-    let client = LauncherClient::new(agent);
-    let price = client.fetch_deployment_price().await?;
-    info!(%price, "fetched deployment price");
+    // The launcher's deploy_instance takes no subaccount and pulls the price from the default
+    // account, so approving from a subaccount would either fail or charge another account.
+    if ctx.subaccount.is_some() {
+        bail!(KinicError::Validation(
+            "create does not support --subaccount: the launcher charges the default account of the identity".to_string()
+        ));
+    }
 
-    let required = required_balance(&price);
+    let client = KinicClient::connect(&ctx.agent_factory).await?;
+    let balance = client.balance().await?;
+    let price = client.deployment_price().await?;
+    let fee = client.ledger_fee().await?;
+    info!(%price, %fee, "fetched deployment price and ledger fee");
+
+    let required = required_balance(&price, &fee);
     let balance_nat = Nat::from(balance);
+
+    if args.dry_run {
+        print_cost_preview(&client, balance, &price, &fee, &required)?;
+        println!("Dry run: no approval or deployment was sent.");
+        return Ok(());
+    }
+
     if balance_nat < required {
-        bail!(
+        bail!(KinicError::Ledger(format!(
             "Insufficient balance: need {} e8s (price + 2 * fee), have {} e8s",
-            required,
-            balance
-        );
+            required, balance
+        )));
+    }
+
+    print_cost_preview(&client, balance, &price, &fee, &required)?;
+    if !confirm("Approve the launcher and deploy this memory?", args.yes)? {
+        println!("Aborted; no tokens were approved.");
+        return Ok(());
     }
 
-    client.approve_launcher(&price).await?;
+    client.approve_deployment(&price, &fee).await?;
     info!("launcher approved to transfer tokens");
 
-    let id = client.deploy_memory(&args.name, &args.description).await?;
+    let id = match client.deploy_memory(&args.name, &args.description).await {
+        Ok(id) => id,
+        Err(err) => {
+            let policy = if args.revoke_on_failure {
+                RevokeOnFailure::Always
+            } else if args.yes {
+                RevokeOnFailure::Never
+            } else {
+                RevokeOnFailure::Ask
+            };
+            offer_launcher_revoke(client.agent(), None, policy).await;
+            return Err(err);
+        }
+    };
     info!(%id, "memory deployed");
     println!("Memory canister id: {id}");
+
+    if args.wait {
+        wait_until_running(&client, &id, Duration::from_secs(args.wait_timeout)).await?;
+        println!("Memory {id} is running.");
+    }
+    Ok(())
+}
+
+/// The approval and the launcher's pull each cost one ledger fee on top of the price.
+fn required_balance(price: &Nat, fee: &Nat) -> Nat {
+    price.clone() + fee.clone() + fee.clone()
+}
+
+fn print_cost_preview(
+    client: &KinicClient,
+    balance: u128,
+    price: &Nat,
+    fee: &Nat,
+    required: &Nat,
+) -> Result<()> {
+    let price = nat_to_u128(price)?;
+    let fee = nat_to_u128(fee)?;
+    let required = nat_to_u128(required)?;
+    let ttl_minutes = APPROVAL_TTL_NS / 60_000_000_000;
+
+    println!("Deployment cost for a new memory:");
+    println!("  Current balance:    {} KINIC", format_kinic(balance));
+    println!("  Deployment price:   {} KINIC", format_kinic(price));
+    println!("  Approval fee:       {} KINIC", format_kinic(fee));
+    println!("  Transfer fee:       {} KINIC", format_kinic(fee));
+    println!("  Total required:     {} KINIC", format_kinic(required));
+    println!(
+        "  Allowance to grant: {} KINIC to launcher {}",
+        format_kinic(price),
+        client.launcher().launcher_id()
+    );
+    println!("  Allowance expiry:   {ttl_minutes} minutes after approval");
+    match balance.checked_sub(required) {
+        Some(remaining) => println!("  Balance afterwards: {} KINIC", format_kinic(remaining)),
+        None => println!(
+            "  Balance afterwards: insufficient (short by {} KINIC)",
+            format_kinic(required - balance)
+        ),
+    }
     Ok(())
 }
 
-fn required_balance(price: &Nat) -> Nat {
-    let fee = Nat::from(TRANSFER_FEE_E8S);
-    price.clone() + fee.clone() + fee
+async fn wait_until_running(
+    client: &KinicClient,
+    instance: &Principal,
+    timeout: Duration,
+) -> Result<()> {
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_style(
+        ProgressStyle::with_template("{spinner} {msg} ({elapsed})")
+            .context("Invalid spinner template")?,
+    );
+    spinner.enable_steady_tick(Duration::from_millis(120));
+    spinner.set_message(format!("Waiting for memory {instance} to start"));
+
+    let started = Instant::now();
+    let mut last_seen: Option<String> = None;
+    loop {
+        match client.memory_state(instance).await {
+            Ok(Some(State::Running(_))) => {
+                spinner.finish_and_clear();
+                return Ok(());
+            }
+            Ok(Some(state)) => {
+                let summary = describe_state(&state);
+                if last_seen.as_deref() != Some(summary.as_str()) {
+                    spinner.println(format!("  state: {summary}"));
+                    spinner.set_message(format!("Memory is in {} state", state.label()));
+                    last_seen = Some(summary);
+                }
+            }
+            Ok(None) => {
+                spinner.set_message("Waiting for the launcher to report the new memory");
+            }
+            Err(err) => {
+                debug!(error = %err, "failed to poll launcher state; retrying");
+            }
+        }
+
+        if started.elapsed() >= timeout {
+            spinner.finish_and_clear();
+            bail!(
+                "Timed out after {}s waiting for memory {instance} to reach Running (last state: {}). It may still finish; check with `kinic-cli list`.",
+                timeout.as_secs(),
+                last_seen.as_deref().unwrap_or("unknown")
+            );
+        }
+        tokio::time::sleep(WAIT_POLL_INTERVAL).await;
+    }
+}
+
+fn describe_state(state: &State) -> String {
+    match state.detail().filter(|text| !text.is_empty()) {
+        Some(message) if state.deploy_metadata().is_none() => {
+            format!("{} ({message})", state.label())
+        }
+        _ => state.label().to_string(),
+    }
 }
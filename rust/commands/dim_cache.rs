@@ -0,0 +1,126 @@
+//! rust/commands/dim_cache.rs
+//! Where: Per-memory embedding dimension cache (~/.config/kinic/memory_dims.json), CLI only.
+//! What: Remembers the vector dimension each memory canister was last seen with.
+//! Why: Each CLI run is a new process; seeding the client from this file lets inserts reject
+//! mismatched embeddings without a metadata query per run. The SDK keeps its cache in memory.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use ic_agent::export::Principal;
+use tracing::{debug, warn};
+
+use crate::kinic_client::KinicClient;
+
+pub fn cached_dim(memory: &Principal) -> Option<usize> {
+    load(&cache_path().ok()?)
+        .ok()?
+        .get(&memory.to_text())
+        .copied()
+}
+
+/// Seeds `client` with the dimension cached on disk for `memory`, if any.
+pub fn seed(client: &KinicClient, memory: &Principal) {
+    if let Some(dim) = cached_dim(memory) {
+        client.remember_dim(*memory, dim);
+    }
+}
+
+/// Saves the dimension `client` learned for `memory` during this run.
+pub fn persist(client: &KinicClient, memory: &Principal) {
+    if let Some(dim) = client.known_dim(memory) {
+        remember_dim(memory, dim);
+    }
+}
+
+/// Records `dim` for `memory`. Failures are only logged since the cache is an optimisation.
+pub fn remember_dim(memory: &Principal, dim: usize) {
+    if let Err(err) = cache_path().and_then(|path| update(&path, memory, dim)) {
+        debug!("could not update dimension cache: {err:#}");
+    }
+}
+
+/// An unreadable or corrupt cache is replaced rather than left to fail every later run.
+fn update(path: &Path, memory: &Principal, dim: usize) -> Result<()> {
+    let mut dims = load(path).unwrap_or_else(|err| {
+        warn!("discarding unreadable dimension cache: {err:#}");
+        BTreeMap::new()
+    });
+    if dims.insert(memory.to_text(), dim) == Some(dim) {
+        return Ok(());
+    }
+    store(path, &dims)
+}
+
+fn load(path: &Path) -> Result<BTreeMap<String, usize>> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let payload =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&payload).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Writes to a per-process temp file and renames it over the cache, so concurrent runs never
+/// leave a half-written file behind.
+fn store(path: &Path, dims: &BTreeMap<String, usize>) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+    fs::write(&tmp, serde_json::to_string_pretty(dims)?)
+        .with_context(|| format!("Failed to write {}", tmp.display()))?;
+    if let Err(err) = fs::rename(&tmp, path) {
+        let _ = fs::remove_file(&tmp);
+        return Err(err).with_context(|| format!("Failed to replace {}", path.display()));
+    }
+    Ok(())
+}
+
+fn cache_path() -> Result<PathBuf> {
+    let home = std::env::var("HOME").context("HOME is not set")?;
+    Ok(PathBuf::from(home).join(".config/kinic/memory_dims.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("kinic-dims-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("memory_dims.json")
+    }
+
+    #[test]
+    fn update_creates_and_extends_the_cache() {
+        let path = scratch("extend");
+        let a = Principal::from_slice(&[1; 29]);
+        let b = Principal::from_slice(&[2; 29]);
+        update(&path, &a, 768).unwrap();
+        update(&path, &b, 1024).unwrap();
+        let dims = load(&path).unwrap();
+        assert_eq!(dims.get(&a.to_text()), Some(&768));
+        assert_eq!(dims.get(&b.to_text()), Some(&1024));
+        let leftovers = fs::read_dir(path.parent().unwrap()).unwrap().count();
+        assert_eq!(leftovers, 1, "temp file should be renamed away");
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn update_replaces_a_corrupt_cache() {
+        let path = scratch("corrupt");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "{not json").unwrap();
+        let memory = Principal::from_slice(&[3; 29]);
+        update(&path, &memory, 384).unwrap();
+        let dims = load(&path).unwrap();
+        assert_eq!(dims.len(), 1);
+        assert_eq!(dims.get(&memory.to_text()), Some(&384));
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,321 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result, anyhow};
+use chrono::DateTime;
+use ic_agent::export::Principal;
+use icrc_ledger_types::{icrc::generic_value::ICRC3Value, icrc1::account::Account};
+use tracing::{debug, info, warn};
+
+use crate::{
+    cli::HistoryArgs,
+    clients::LAUNCHER_CANISTER,
+    ledger::{LedgerOperation, LedgerTransaction, fetch_blocks, fetch_log_length, format_kinic},
+};
+
+use super::CommandContext;
+
+const BLOCKS_PER_PAGE: u64 = 1_000;
+
+pub async fn handle(args: HistoryArgs, ctx: &CommandContext) -> Result<()> {
+    let agent = ctx.agent_factory.build().await?;
+    let principal = agent
+        .get_principal()
+        .map_err(|e| anyhow!("Failed to derive principal for current identity: {e}"))?;
+    let account = Account {
+        owner: principal,
+        subaccount: ctx.subaccount,
+    };
+    let launcher = Account::from(
+        Principal::from_text(LAUNCHER_CANISTER).context("Failed to parse launcher canister id")?,
+    );
+
+    let log_length = fetch_log_length(&agent).await?;
+    let floor = log_length.saturating_sub(args.max_blocks);
+    let mut end = log_length;
+    let mut entries: Vec<HistoryEntry> = Vec::new();
+    let mut skipped = 0;
+
+    // Walk the log backwards so the newest transactions are found first.
+    while end > floor && entries.len() < args.limit {
+        let start = end.saturating_sub(BLOCKS_PER_PAGE).max(floor);
+        // `fetch_blocks` returns every block in the range or fails, so no block is skipped.
+        let blocks = fetch_blocks(&agent, start, end - start).await?;
+        skipped += collect_entries(&blocks, &account, &launcher, &mut entries);
+        end = start;
+    }
+    entries.truncate(args.limit);
+    if skipped > 0 {
+        warn!(
+            skipped,
+            "skipped ledger blocks that are not ICRC-1/ICRC-2 transactions"
+        );
+    }
+
+    info!(
+        %account,
+        scanned_blocks = log_length - end,
+        found = entries.len(),
+        "ledger history fetched"
+    );
+
+    if entries.is_empty() {
+        println!(
+            "No transactions for {account} in the last {} blocks.",
+            log_length - end
+        );
+    } else {
+        println!("Transactions for {account} (newest first):");
+        for entry in &entries {
+            println!(
+                "- #{} {} {:<12} {:>20} fee {:<12} {}",
+                entry.tx.index,
+                format_timestamp(entry.tx.timestamp_ns),
+                entry.kind,
+                entry.signed_amount(),
+                entry.fee_paid().unwrap_or_else(|| "-".to_string()),
+                entry
+                    .counterparty
+                    .map(|account| account.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+            );
+        }
+    }
+
+    if let Some(path) = &args.csv {
+        write_csv(path, &entries)?;
+        println!("Wrote {} transactions to {}", entries.len(), path.display());
+    }
+    Ok(())
+}
+
+/// Appends the entries for `account` from one page of blocks, newest first. Blocks that do
+/// not decode as a transaction (fee-collector updates, unknown btypes, malformed accounts)
+/// are skipped rather than ending the scan; returns how many were skipped.
+fn collect_entries(
+    blocks: &[(u64, ICRC3Value)],
+    account: &Account,
+    launcher: &Account,
+    entries: &mut Vec<HistoryEntry>,
+) -> usize {
+    let mut skipped = 0;
+    for (index, block) in blocks.iter().rev() {
+        match LedgerTransaction::from_block(*index, block) {
+            Ok(tx) if tx.involves(account) => {
+                entries.push(HistoryEntry::new(tx, account, launcher))
+            }
+            Ok(_) => {}
+            Err(err) => {
+                debug!(block = index, error = %err, "skipping ledger block");
+                skipped += 1;
+            }
+        }
+    }
+    skipped
+}
+
+/// How a transaction moves the current account's balance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Direction {
+    Outgoing,
+    Incoming,
+    /// The account only spent someone else's allowance; its own balance is unchanged.
+    Spender,
+    Approval,
+}
+
+struct HistoryEntry {
+    tx: LedgerTransaction,
+    kind: &'static str,
+    direction: Direction,
+    pays_fee: bool,
+    counterparty: Option<Account>,
+}
+
+impl HistoryEntry {
+    fn new(tx: LedgerTransaction, account: &Account, launcher: &Account) -> Self {
+        let from_me = tx.from.as_ref() == Some(account);
+        let direction = if matches!(tx.operation, LedgerOperation::Approve) {
+            Direction::Approval
+        } else if from_me {
+            Direction::Outgoing
+        } else if tx.to.as_ref() == Some(account) || tx.spender.as_ref() != Some(account) {
+            Direction::Incoming
+        } else {
+            Direction::Spender
+        };
+        let (kind, counterparty) = match &tx.operation {
+            LedgerOperation::Transfer if from_me && tx.spender.as_ref() == Some(launcher) => {
+                ("deployment", tx.spender)
+            }
+            LedgerOperation::Transfer if from_me && tx.spender.is_some() => {
+                ("transfer_from", tx.spender)
+            }
+            LedgerOperation::Transfer if from_me => ("transfer_out", tx.to),
+            LedgerOperation::Transfer if direction == Direction::Spender => {
+                ("spent_allowance", tx.from)
+            }
+            LedgerOperation::Transfer => ("transfer_in", tx.from),
+            LedgerOperation::Approve => ("approval", tx.spender),
+            LedgerOperation::Mint => ("mint", None),
+            LedgerOperation::Burn => ("burn", None),
+            LedgerOperation::Other(_) => ("other", None),
+        };
+        Self {
+            tx,
+            kind,
+            direction,
+            pays_fee: from_me,
+            counterparty,
+        }
+    }
+
+    fn signed_amount(&self) -> String {
+        let amount = format_kinic(self.tx.amount);
+        match self.direction {
+            Direction::Approval => format!("allow {amount}"),
+            Direction::Spender => format!("spent {amount}"),
+            Direction::Outgoing => format!("-{amount}"),
+            Direction::Incoming => format!("+{amount}"),
+        }
+    }
+
+    fn fee_paid(&self) -> Option<String> {
+        self.tx.fee.filter(|_| self.pays_fee).map(format_kinic)
+    }
+}
+
+fn format_timestamp(timestamp_ns: u64) -> String {
+    let secs = (timestamp_ns / 1_000_000_000) as i64;
+    let nanos = (timestamp_ns % 1_000_000_000) as u32;
+    DateTime::from_timestamp(secs, nanos)
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| timestamp_ns.to_string())
+}
+
+fn write_csv(path: &Path, entries: &[HistoryEntry]) -> Result<()> {
+    let mut csv =
+        String::from("block,timestamp,kind,from,to,spender,amount_kinic,fee_kinic,memo\n");
+    for entry in entries {
+        let tx = &entry.tx;
+        let fields = [
+            tx.index.to_string(),
+            format_timestamp(tx.timestamp_ns),
+            entry.kind.to_string(),
+            tx.from.map(|a| a.to_string()).unwrap_or_default(),
+            tx.to.map(|a| a.to_string()).unwrap_or_default(),
+            tx.spender.map(|a| a.to_string()).unwrap_or_default(),
+            entry.signed_amount(),
+            entry.fee_paid().unwrap_or_default(),
+            tx.memo.as_deref().map(format_memo).unwrap_or_default(),
+        ];
+        let line = fields
+            .iter()
+            .map(|field| csv_escape(field))
+            .collect::<Vec<_>>()
+            .join(",");
+        csv.push_str(&line);
+        csv.push('\n');
+    }
+    fs::write(path, csv).with_context(|| format!("Failed to write CSV to {}", path.display()))
+}
+
+fn format_memo(memo: &[u8]) -> String {
+    match std::str::from_utf8(memo) {
+        Ok(text) if !text.chars().any(char::is_control) => text.to_string(),
+        _ => hex::encode(memo),
+    }
+}
+
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use candid::Nat;
+    use icrc_ledger_types::icrc::generic_value::Value;
+
+    use super::*;
+
+    fn account(id: u8) -> Account {
+        Account::from(Principal::from_slice(&[id; 29]))
+    }
+
+    fn transfer(from: Account, to: Account, spender: Option<Account>) -> ICRC3Value {
+        let mut tx = vec![
+            ("op", Value::text("xfer")),
+            ("from", Value::from(from)),
+            ("to", Value::from(to)),
+            ("amt", Value::Nat(Nat::from(500u64))),
+        ];
+        if let Some(spender) = spender {
+            tx.push(("spender", Value::from(spender)));
+        }
+        Value::map([("tx", Value::map(tx)), ("ts", Value::Nat(Nat::from(1u64)))]).into()
+    }
+
+    #[test]
+    fn non_transaction_blocks_are_skipped() {
+        let me = account(1);
+        let other = account(2);
+        let fee_collector = Value::map([
+            ("btype", Value::text("107feecol")),
+            ("ts", Value::Nat(Nat::from(1u64))),
+        ])
+        .into();
+        let blocks = vec![
+            (10, transfer(other, me, None)),
+            (11, fee_collector),
+            (12, ICRC3Value::Text("not a block".to_string())),
+            (13, transfer(me, other, None)),
+        ];
+
+        let mut entries = Vec::new();
+        let skipped = collect_entries(&blocks, &me, &account(9), &mut entries);
+
+        assert_eq!(skipped, 2);
+        let indexes: Vec<u64> = entries.iter().map(|entry| entry.tx.index).collect();
+        assert_eq!(indexes, vec![13, 10]);
+    }
+
+    #[test]
+    fn spender_side_transfer_from_does_not_credit_the_spender() {
+        let me = account(1);
+        let (owner, recipient) = (account(2), account(3));
+        let tx = LedgerTransaction::from_block(0, &transfer(owner, recipient, Some(me))).unwrap();
+
+        let entry = HistoryEntry::new(tx, &me, &account(9));
+
+        assert_eq!(entry.kind, "spent_allowance");
+        assert_eq!(entry.direction, Direction::Spender);
+        assert_eq!(entry.counterparty, Some(owner));
+        assert!(entry.signed_amount().starts_with("spent "));
+        assert_eq!(entry.fee_paid(), None);
+    }
+
+    #[test]
+    fn transfer_from_directions_follow_the_account() {
+        let (owner, spender, recipient) = (account(1), account(2), account(3));
+        let block = transfer(owner, recipient, Some(spender));
+
+        let as_owner = HistoryEntry::new(
+            LedgerTransaction::from_block(0, &block).unwrap(),
+            &owner,
+            &account(9),
+        );
+        assert_eq!(as_owner.kind, "transfer_from");
+        assert!(as_owner.signed_amount().starts_with('-'));
+
+        let as_recipient = HistoryEntry::new(
+            LedgerTransaction::from_block(0, &block).unwrap(),
+            &recipient,
+            &account(9),
+        );
+        assert_eq!(as_recipient.kind, "transfer_in");
+        assert!(as_recipient.signed_amount().starts_with('+'));
+    }
+}
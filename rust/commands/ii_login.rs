@@ -4,12 +4,15 @@
 //! Why: Avoids requiring a keychain-backed dfx identity for CLI-only login.
 
 use std::{
+    fs,
+    io::{BufRead, Write},
     net::SocketAddr,
+    path::{Path, PathBuf},
     sync::Arc,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result, anyhow, bail};
 use axum::{
     Json, Router,
     body::Bytes,
@@ -21,31 +24,37 @@ use axum::{
     response::{Html, IntoResponse},
     routing::{get, post},
 };
-use ic_agent::export::Principal;
+use base64::{Engine, prelude::BASE64_STANDARD};
+use ic_agent::export::{Principal, reqwest::Url};
 use ic_agent::identity::{Delegation, SignedDelegation};
 use ring::rand::{SecureRandom, SystemRandom};
 use serde::Deserialize;
 use serde_json::json;
 use tokio::{
     net::TcpListener,
-    sync::{Mutex, oneshot},
+    sync::{Mutex, mpsc, oneshot},
 };
+use tracing::debug;
 
 use crate::{
-    cli::LoginArgs,
+    cli::{LoginArgs, SessionProtection},
     commands::CommandContext,
+    error::KinicError,
     identity_store::{
-        SessionKeyMaterial, StoredIdentity, derive_principal_from_user_key, generate_session_key,
-        normalize_spki_key, save_identity,
+        EXPIRY_WARNING_NS, KeyProtection, ProtectionMode, SessionKeyMaterial, StoredIdentity,
+        derive_principal_from_user_key, forget_protected_key, generate_session_key,
+        normalize_spki_key, protect_session_key, read_stored_identity, remaining_validity_ns,
+        save_identity,
     },
 };
 
-const IDENTITY_PROVIDER_URL: &str = "https://id.ai/#authorize";
-const IDENTITY_PROVIDER_ORIGIN: &str = "https://id.ai";
-const CALLBACK_PORT: u16 = 8620;
-const CALLBACK_TIMEOUT_SECS: u64 = 300;
+const DEFAULT_IDENTITY_PROVIDER_URL: &str = "https://id.ai/#authorize";
+const DEFAULT_CALLBACK_PORT: u16 = 8620;
+const DEFAULT_CALLBACK_TIMEOUT_SECS: u64 = 300;
 const MAX_CALLBACK_BODY_BYTES: usize = 256 * 1024;
 const DEFAULT_TTL_HOURS: u64 = 6;
+// Internet Identity caps delegations at 30 days.
+const MAX_TTL_HOURS: u64 = 30 * 24;
 const SECONDS_PER_HOUR: u64 = 3_600;
 const NANOS_PER_SECOND: u64 = 1_000_000_000;
 
@@ -83,33 +92,137 @@ struct BrowserDelegation {
     targets: Option<Vec<String>>,
 }
 
-pub async fn handle(_args: LoginArgs, ctx: &CommandContext) -> Result<()> {
+/// Settings for one run of the browser login flow.
+pub struct LoginOptions {
+    pub ttl_hours: u64,
+    pub port: u16,
+    pub provider_url: String,
+    pub timeout_secs: u64,
+    pub no_browser: bool,
+    /// With `no_browser`, also accept the login blob pasted into stdin. The Python binding
+    /// leaves this off so a login never reads the host process's stdin.
+    pub paste_blob: bool,
+    pub protection: ProtectionMode,
+}
+
+impl Default for LoginOptions {
+    fn default() -> Self {
+        Self {
+            ttl_hours: DEFAULT_TTL_HOURS,
+            port: DEFAULT_CALLBACK_PORT,
+            provider_url: DEFAULT_IDENTITY_PROVIDER_URL.to_string(),
+            timeout_secs: DEFAULT_CALLBACK_TIMEOUT_SECS,
+            no_browser: false,
+            paste_blob: false,
+            protection: ProtectionMode::None,
+        }
+    }
+}
+
+impl LoginOptions {
+    fn from_args(args: &LoginArgs) -> Self {
+        Self {
+            ttl_hours: args.ttl,
+            port: args.port,
+            provider_url: args.provider_url.clone(),
+            timeout_secs: args.timeout,
+            no_browser: args.no_browser,
+            paste_blob: args.no_browser,
+            protection: protection_mode(args.protect),
+        }
+    }
+}
+
+pub async fn handle(args: LoginArgs, ctx: &CommandContext) -> Result<()> {
     let identity_path = ctx
         .identity_path
         .clone()
         .ok_or_else(|| anyhow!("Identity path is missing"))?;
-    let ttl_ns = ttl_nanos()?;
+    login(&identity_path, &LoginOptions::from_args(&args)).await?;
+    Ok(())
+}
+
+/// Runs the login flow again when the stored delegation is missing, unreadable, or close to
+/// expiry. Used by `--relogin` before commands load the delegation; the previous provider,
+/// callback port, TTL and protection are reused so the refreshed delegation has the same
+/// principal and lifetime as the old one.
+pub async fn relogin_if_needed(identity_path: &Path) -> Result<()> {
+    let mut options = LoginOptions::default();
+    let remaining_ns = match read_stored_identity(identity_path) {
+        Ok(stored) => {
+            options.provider_url = stored.identity_provider.clone();
+            options.port = stored.callback_port.unwrap_or(DEFAULT_CALLBACK_PORT);
+            options.ttl_hours = stored.ttl_hours.unwrap_or(DEFAULT_TTL_HOURS);
+            options.protection = stored.protection.mode();
+            remaining_validity_ns(&stored)?
+        }
+        Err(err) => {
+            debug!("stored delegation unusable, logging in again: {err:#}");
+            0
+        }
+    };
+    if remaining_ns >= EXPIRY_WARNING_NS {
+        return Ok(());
+    }
+    eprintln!("Internet Identity delegation is missing or about to expire; starting login.");
+    login(identity_path, &options).await?;
+    Ok(())
+}
+
+/// Runs the browser flow and saves the delegation to `identity_path`, returning the principal.
+pub(crate) async fn login(identity_path: &Path, options: &LoginOptions) -> Result<Principal> {
+    if options.ttl_hours == 0 || options.ttl_hours > MAX_TTL_HOURS {
+        bail!(KinicError::Validation(format!(
+            "--ttl must be between 1 and {MAX_TTL_HOURS} hours"
+        )));
+    }
+    let provider_origin = provider_origin(&options.provider_url)?;
+    let ttl_ns = ttl_nanos(options.ttl_hours)?;
     // CSRF mitigation: random state token is generated per session and verified on callback.
     let state_token = generate_state()?;
     // Session key is generated locally and shared with the browser page.
     let session = generate_session_key()?;
     let session_pubkey = normalize_spki_key(&session.public_key)?;
-    let html = build_login_page(&session, ttl_ns, &state_token);
 
     // Bind a local callback port for the browser to send delegations back.
-    let addr = SocketAddr::from(([127, 0, 0, 1], CALLBACK_PORT));
+    let addr = SocketAddr::from(([127, 0, 0, 1], options.port));
     let listener = match TcpListener::bind(addr).await {
         Ok(listener) => listener,
         Err(err) if err.kind() == std::io::ErrorKind::AddrInUse => {
             anyhow::bail!(
-                "Failed to bind to {addr}: port {port} is already in use. Stop the process using it and try again.",
-                port = CALLBACK_PORT
+                "Failed to bind to {addr}: port {port} is already in use. Stop the process using it or pass --port.",
+                port = options.port
             );
         }
         Err(err) => {
             return Err(err).with_context(|| format!("Failed to bind to {addr}"));
         }
     };
+    let port = listener
+        .local_addr()
+        .context("Failed to read callback address")?
+        .port();
+    if port != DEFAULT_CALLBACK_PORT {
+        eprintln!(
+            "Note: callback port {port} differs from {DEFAULT_CALLBACK_PORT}; Internet Identity derives a different principal for each origin."
+        );
+    }
+    let paste_blob = options.no_browser && options.paste_blob;
+    let html = build_login_page(
+        &session,
+        ttl_ns,
+        &state_token,
+        &options.provider_url,
+        &provider_origin,
+        paste_blob,
+    );
+    let expected_state = state_token.clone();
+    // Headless logins also get the page as a file, to be served on the browser's machine.
+    let page_file = if paste_blob {
+        Some(write_login_page(&html, port)?)
+    } else {
+        None
+    };
 
     let (callback_tx, callback_rx) = oneshot::channel();
     let (shutdown_tx, shutdown_rx) = oneshot::channel();
@@ -136,14 +249,46 @@ pub async fn handle(_args: LoginArgs, ctx: &CommandContext) -> Result<()> {
         }
     });
 
-    // Launch the browser so the user can authenticate with Internet Identity.
-    open_browser(CALLBACK_PORT)?;
+    let wait_for_login = async {
+        if !options.no_browser {
+            // Launch the browser so the user can authenticate with Internet Identity.
+            open_browser(port)?;
+            // Block until the browser posts back the delegation payload.
+            return callback_rx
+                .await
+                .map_err(|_| anyhow!("Login callback channel closed"));
+        }
 
-    // Block until the browser posts back the delegation payload.
-    let callback = tokio::time::timeout(Duration::from_secs(CALLBACK_TIMEOUT_SECS), callback_rx)
-        .await
-        .map_err(|_| anyhow!("Login timed out waiting for browser callback"))?
-        .map_err(|_| anyhow!("Login callback channel closed"))?;
+        print_headless_instructions(port, page_file.as_deref());
+        if !paste_blob {
+            return callback_rx
+                .await
+                .map_err(|_| anyhow!("Login callback channel closed"));
+        }
+        // Whichever arrives first wins: the forwarded callback or a blob pasted into stdin.
+        // Dropping `pasted` when login ends tells the reader thread to stop.
+        let mut pasted = spawn_paste_reader();
+        tokio::select! {
+            callback = callback_rx => callback.map_err(|_| anyhow!("Login callback channel closed")),
+            data = async {
+                while let Some(blob) = pasted.recv().await {
+                    match decode_pasted_payload(&blob, &expected_state) {
+                        Ok(data) => return Ok(data),
+                        Err(err) => eprintln!("Rejected pasted blob: {err:#}. Paste it again."),
+                    }
+                }
+                // stdin closed; keep waiting for the forwarded callback instead.
+                std::future::pending().await
+            } => data,
+        }
+    };
+    let outcome =
+        tokio::time::timeout(Duration::from_secs(options.timeout_secs), wait_for_login).await;
+    if let Some(path) = &page_file {
+        let _ = fs::remove_file(path);
+    }
+    let callback =
+        outcome.map_err(|_| anyhow!("Login timed out waiting for browser callback"))??;
 
     let _ = shutdown_tx.send(());
     let _ = server_handle.await;
@@ -151,36 +296,168 @@ pub async fn handle(_args: LoginArgs, ctx: &CommandContext) -> Result<()> {
     let delegations = convert_delegations(callback.payload.delegations, &session_pubkey)?;
     let expiration_ns = delegation_expiration(&delegations)?;
     let principal = callback.principal;
-    let stored = StoredIdentity {
+    let mut stored = StoredIdentity {
         version: 1,
-        identity_provider: IDENTITY_PROVIDER_URL.to_string(),
+        identity_provider: options.provider_url.clone(),
         user_public_key_hex: hex::encode(callback.payload.user_public_key),
-        session_pkcs8_hex: hex::encode(session.pkcs8),
+        session_pkcs8_hex: String::new(),
         delegations,
         expiration_ns,
         created_at_ns: current_time_ns()?,
+        protection: KeyProtection::None,
+        callback_port: Some(port),
+        ttl_hours: Some(options.ttl_hours),
     };
-    save_identity(&identity_path, &stored)?;
+    protect_session_key(&mut stored, &session.pkcs8, options.protection)?;
+    // Replacing a keyring-protected session must not leave its old entry behind.
+    let previous = read_stored_identity(identity_path).ok();
+    save_identity(identity_path, &stored)?;
+    if let Some(previous) = previous
+        && let Err(err) = forget_protected_key(&previous.protection)
+    {
+        eprintln!("Warning: {err:#}");
+    }
     println!(
         "Saved Internet Identity delegation to {}",
         identity_path.display()
     );
     println!("Principal: {}", principal);
-    Ok(())
+    Ok(principal)
 }
 
-fn build_login_page(session: &SessionKeyMaterial, ttl_ns: u64, state: &str) -> String {
+fn build_login_page(
+    session: &SessionKeyMaterial,
+    ttl_ns: u64,
+    state: &str,
+    provider_url: &str,
+    provider_origin: &str,
+    show_paste_blob: bool,
+) -> String {
     let session_public_key_hex = hex::encode(&session.public_key);
     let template = include_str!(concat!(
         env!("CARGO_MANIFEST_DIR"),
         "/rust/commands/ii_login_page.html"
     ));
     template
-        .replace("{{II_URL}}", IDENTITY_PROVIDER_URL)
-        .replace("{{II_ORIGIN}}", IDENTITY_PROVIDER_ORIGIN)
+        .replace("{{II_URL}}", provider_url)
+        .replace("{{II_ORIGIN}}", provider_origin)
         .replace("{{SESSION_KEY_HEX}}", &session_public_key_hex)
         .replace("{{STATE}}", state)
         .replace("{{TTL_NS}}", &ttl_ns.to_string())
+        .replace("{{SHOW_PASTE_BLOB}}", &show_paste_blob.to_string())
+}
+
+/// Saves the login page so it can be copied to the machine that runs the browser.
+fn write_login_page(html: &str, port: u16) -> Result<PathBuf> {
+    let path = std::env::temp_dir().join(format!("kinic-login-{port}.html"));
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(&path)
+        .and_then(|mut file| file.write_all(html.as_bytes()))
+        .with_context(|| format!("Failed to write login page to {}", path.display()))?;
+    Ok(path)
+}
+
+fn print_headless_instructions(port: u16, page_file: Option<&Path>) {
+    // II derives the principal from http://127.0.0.1:<port>, so every option keeps that origin.
+    let url = format!("http://127.0.0.1:{port}/");
+    if page_file.is_some() {
+        eprintln!("Browser launch disabled. Log in one of these ways:");
+    } else {
+        eprintln!("Browser launch disabled.");
+    }
+    eprintln!("1. From a browser that can reach this machine's port {port}, open:");
+    eprintln!("     {url}");
+    eprintln!("   From another machine, forward the port first, keeping the same port number:");
+    eprintln!("     ssh -L {port}:127.0.0.1:{port} <user>@<this-host>");
+    if let Some(path) = page_file {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        eprintln!(
+            "2. Without port forwarding, copy the login page to the browser's machine and serve"
+        );
+        eprintln!("   it there on the same port:");
+        eprintln!("     scp <user>@<this-host>:{} .", path.display());
+        eprintln!("     python3 -m http.server {port} --bind 127.0.0.1");
+        eprintln!("   Open {url}{name}, log in, then paste the blob the page shows here and");
+        eprintln!("   finish with an empty line.");
+    }
+}
+
+/// Reads pasted blobs from stdin on a dedicated thread; a blob ends at the first empty line.
+/// The thread stops at the next line once the receiver is dropped, so nothing more is read
+/// from stdin after login finishes.
+fn spawn_paste_reader() -> mpsc::UnboundedReceiver<String> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        let mut blob = String::new();
+        for line in std::io::stdin().lock().lines() {
+            if tx.is_closed() {
+                return;
+            }
+            let Ok(line) = line else { break };
+            let line = line.trim();
+            if !line.is_empty() {
+                blob.push_str(line);
+                continue;
+            }
+            if !blob.is_empty() && tx.send(std::mem::take(&mut blob)).is_err() {
+                break;
+            }
+        }
+        if !blob.is_empty() {
+            let _ = tx.send(blob);
+        }
+    });
+    rx
+}
+
+/// Decodes a pasted blob and runs the same checks as the HTTP callback.
+fn decode_pasted_payload(blob: &str, expected_state: &str) -> Result<CallbackData> {
+    let bytes = BASE64_STANDARD
+        .decode(blob.trim())
+        .context("Blob is not valid base64")?;
+    let payload: BrowserPayload =
+        serde_json::from_slice(&bytes).context("Blob does not contain a login payload")?;
+    accept_payload(payload, expected_state).map_err(|reason| anyhow!(reason))
+}
+
+/// Checks the state token and user key shared by every way a payload can arrive.
+fn accept_payload(
+    payload: BrowserPayload,
+    expected_state: &str,
+) -> Result<CallbackData, &'static str> {
+    if payload.state != expected_state {
+        return Err("Invalid state");
+    }
+    let principal = derive_principal_from_user_key(&payload.user_public_key)
+        .map_err(|_| "Invalid public key")?;
+    Ok(CallbackData { payload, principal })
+}
+
+pub fn protection_mode(protection: SessionProtection) -> ProtectionMode {
+    match protection {
+        SessionProtection::None => ProtectionMode::None,
+        SessionProtection::Keyring => ProtectionMode::Keyring,
+        SessionProtection::Passphrase => ProtectionMode::Passphrase,
+    }
+}
+
+/// The page only accepts messages from the provider's origin, so derive it from the URL.
+fn provider_origin(provider_url: &str) -> Result<String> {
+    let url = Url::parse(provider_url)
+        .with_context(|| format!("Invalid identity provider URL: {provider_url}"))?;
+    if !matches!(url.scheme(), "https" | "http") {
+        bail!(KinicError::Validation(
+            "Identity provider URL must use http or https".to_string()
+        ));
+    }
+    Ok(url.origin().ascii_serialization())
 }
 
 async fn root_handler(State(state): State<Arc<CallbackState>>) -> Html<String> {
@@ -245,21 +522,17 @@ async fn callback_handler(
         }
     };
 
-    if payload.state != state.expected_state {
-        return (StatusCode::BAD_REQUEST, "Invalid state".to_string()).into_response();
-    }
-
-    let principal = match derive_principal_from_user_key(&payload.user_public_key) {
-        Ok(principal) => principal,
-        Err(_) => {
-            return (StatusCode::BAD_REQUEST, "Invalid public key".to_string()).into_response();
+    let data = match accept_payload(payload, &state.expected_state) {
+        Ok(data) => data,
+        Err(reason) => {
+            return (StatusCode::BAD_REQUEST, reason.to_string()).into_response();
         }
     };
-    let principal_text = principal.to_text();
+    let principal_text = data.principal.to_text();
 
     let mut sender = state.sender.lock().await;
     if let Some(tx) = sender.take() {
-        let _ = tx.send(CallbackData { payload, principal });
+        let _ = tx.send(data);
     } else {
         return (StatusCode::CONFLICT, "Login already completed".to_string()).into_response();
     }
@@ -326,8 +599,8 @@ fn delegation_expiration(entries: &[SignedDelegation]) -> Result<u64> {
     Ok(expiration)
 }
 
-fn ttl_nanos() -> Result<u64> {
-    let ttl_seconds = DEFAULT_TTL_HOURS
+fn ttl_nanos(ttl_hours: u64) -> Result<u64> {
+    let ttl_seconds = ttl_hours
         .checked_mul(SECONDS_PER_HOUR)
         .ok_or_else(|| anyhow!("TTL overflow"))?;
     ttl_seconds
@@ -411,3 +684,57 @@ fn generate_state() -> Result<String> {
         .map_err(|_| anyhow!("Failed to generate state token"))?;
     Ok(hex::encode(state_bytes))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blob(user_key: &[u8], state: &str) -> String {
+        let payload = json!({
+            "delegations": [],
+            "userPublicKey": user_key,
+            "state": state,
+        });
+        BASE64_STANDARD.encode(payload.to_string())
+    }
+
+    fn user_key() -> Vec<u8> {
+        generate_session_key().unwrap().public_key
+    }
+
+    #[test]
+    fn pasted_blob_is_accepted_with_matching_state() {
+        let key = user_key();
+        let data = decode_pasted_payload(&blob(&key, "token"), "token").unwrap();
+        assert_eq!(
+            data.principal,
+            derive_principal_from_user_key(&key).unwrap()
+        );
+        assert!(data.payload.delegations.is_empty());
+    }
+
+    #[test]
+    fn pasted_blob_tolerates_surrounding_whitespace() {
+        let padded = format!("  {}\n", blob(&user_key(), "token"));
+        assert!(decode_pasted_payload(&padded, "token").is_ok());
+    }
+
+    #[test]
+    fn pasted_blob_with_other_state_is_rejected() {
+        assert!(decode_pasted_payload(&blob(&user_key(), "other"), "token").is_err());
+    }
+
+    #[test]
+    fn pasted_garbage_is_rejected() {
+        assert!(decode_pasted_payload("not base64!", "token").is_err());
+        let not_json = BASE64_STANDARD.encode("hello");
+        assert!(decode_pasted_payload(&not_json, "token").is_err());
+    }
+
+    #[test]
+    fn pasted_blob_with_bad_user_key_is_rejected() {
+        let payload = json!({ "delegations": [], "userPublicKey": [1, 2, 3], "state": "token" });
+        let blob = BASE64_STANDARD.encode(payload.to_string());
+        assert!(decode_pasted_payload(&blob, "token").is_err());
+    }
+}
@@ -2,20 +2,24 @@ use std::fs;
 
 use anyhow::{Context, Result, bail};
 use ic_agent::export::Principal;
-use serde_json::json;
 use tracing::info;
 
-use crate::{cli::InsertArgs, clients::memory::MemoryClient, embedding::late_chunking};
+use crate::{
+    cli::InsertArgs, embedding::late_chunking, error::KinicError, kinic_client::KinicClient,
+};
 
-use super::CommandContext;
+use super::{CommandContext, dim_cache};
 
 pub async fn handle(args: InsertArgs, ctx: &CommandContext) -> Result<()> {
-    let client = build_memory_client(&args.memory_id, ctx).await?;
+    let client = KinicClient::connect(&ctx.agent_factory).await?;
+    let memory = Principal::from_text(&args.memory_id)
+        .context("Failed to parse canister id for insert command")?;
     let content = load_insert_content(&args)?;
     let chunks = late_chunking(&content).await?;
+    dim_cache::seed(&client, &memory);
 
     info!(
-        canister_id = %client.canister_id(),
+        canister_id = %memory,
         chunk_count = chunks.len(),
         tag = %args.tag,
         source = %insert_source(&args),
@@ -23,7 +27,6 @@ pub async fn handle(args: InsertArgs, ctx: &CommandContext) -> Result<()> {
     );
 
     for (index, chunk) in chunks.into_iter().enumerate() {
-        let payload = format_chunk_text(&args.tag, &chunk.sentence);
         info!(
             chunk_index = index,
             sentence_preview = %chunk
@@ -33,23 +36,15 @@ pub async fn handle(args: InsertArgs, ctx: &CommandContext) -> Result<()> {
                 .collect::<String>(),
             "inserting chunk"
         );
-        client.insert(chunk.embedding, &payload).await?;
+        client
+            .insert_embedding(&memory, &args.tag, &chunk.sentence, chunk.embedding)
+            .await?;
     }
 
+    dim_cache::persist(&client, &memory);
     Ok(())
 }
 
-async fn build_memory_client(id: &str, ctx: &CommandContext) -> Result<MemoryClient> {
-    let agent = ctx.agent_factory.build().await?;
-    let memory =
-        Principal::from_text(id).context("Failed to parse canister id for insert command")?;
-    Ok(MemoryClient::new(agent, memory))
-}
-
-fn format_chunk_text(tag: &str, sentence: &str) -> String {
-    json!({ "tag": tag, "sentence": sentence }).to_string()
-}
-
 fn load_insert_content(args: &InsertArgs) -> Result<String> {
     if let Some(text) = &args.text {
         return Ok(text.clone());
@@ -60,7 +55,9 @@ fn load_insert_content(args: &InsertArgs) -> Result<String> {
             .with_context(|| format!("Failed to read --file-path {}", path.display()));
     }
 
-    bail!("Either --text or --file-path must be provided");
+    bail!(KinicError::Validation(
+        "Either --text or --file-path must be provided".to_string()
+    ));
 }
 
 fn insert_source(args: &InsertArgs) -> &'static str {
@@ -3,14 +3,19 @@ use ic_agent::export::Principal;
 use tracing::info;
 
 use crate::{
-    cli::InsertPdfArgs, clients::memory::MemoryClient, commands::convert_pdf::pdf_to_markdown,
+    cli::InsertPdfArgs,
+    commands::convert_pdf::pdf_to_markdown,
     embedding::late_chunking,
+    kinic_client::{KinicClient, tagged_text},
 };
 
-use super::CommandContext;
+use super::{CommandContext, dim_cache};
 
 pub async fn handle(args: InsertPdfArgs, ctx: &CommandContext) -> Result<()> {
-    let client = build_memory_client(&args.memory_id, ctx).await?;
+    let kinic = KinicClient::connect(&ctx.agent_factory).await?;
+    let memory = Principal::from_text(&args.memory_id)
+        .context("Failed to parse canister id for insert-pdf command")?;
+    let client = kinic.memory(memory);
     let markdown = pdf_to_markdown(&args.file_path).map_err(|e| {
         anyhow!(
             "Failed to convert PDF {} to markdown: {e}",
@@ -19,6 +24,7 @@ pub async fn handle(args: InsertPdfArgs, ctx: &CommandContext) -> Result<()> {
     })?;
 
     let chunks = late_chunking(&markdown).await?;
+    dim_cache::seed(&kinic, &memory);
 
     info!(
         canister_id = %client.canister_id(),
@@ -29,7 +35,7 @@ pub async fn handle(args: InsertPdfArgs, ctx: &CommandContext) -> Result<()> {
     );
 
     for (index, chunk) in chunks.into_iter().enumerate() {
-        let payload = format_chunk_text(&args.tag, &chunk.sentence);
+        let payload = tagged_text(&args.tag, &chunk.sentence);
         info!(
             chunk_index = index,
             sentence_preview = %chunk
@@ -42,16 +48,6 @@ pub async fn handle(args: InsertPdfArgs, ctx: &CommandContext) -> Result<()> {
         client.insert(chunk.embedding, &payload).await?;
     }
 
+    dim_cache::persist(&kinic, &memory);
     Ok(())
 }
-
-async fn build_memory_client(id: &str, ctx: &CommandContext) -> Result<MemoryClient> {
-    let agent = ctx.agent_factory.build().await?;
-    let memory =
-        Principal::from_text(id).context("Failed to parse canister id for insert-pdf command")?;
-    Ok(MemoryClient::new(agent, memory))
-}
-
-fn format_chunk_text(tag: &str, sentence: &str) -> String {
-    serde_json::json!({ "tag": tag, "sentence": sentence }).to_string()
-}
@@ -1,44 +1,42 @@
 use anyhow::{Context, Result, bail};
 use ic_agent::export::Principal;
-use serde_json::json;
 use tracing::info;
 
-use crate::{cli::InsertRawArgs, clients::memory::MemoryClient};
+use crate::{cli::InsertRawArgs, error::KinicError, kinic_client::KinicClient};
 
-use super::CommandContext;
+use super::{CommandContext, dim_cache};
 
 pub async fn handle(args: InsertRawArgs, ctx: &CommandContext) -> Result<()> {
-    let client = build_memory_client(&args.memory_id, ctx).await?;
+    let client = KinicClient::connect(&ctx.agent_factory).await?;
+    let memory = Principal::from_text(&args.memory_id)
+        .context("Failed to parse canister id for insert-raw command")?;
     let embedding = parse_embedding(&args.embedding)?;
-    let payload = format_chunk_text(&args.tag, &args.text);
 
     info!(
-        canister_id = %client.canister_id(),
+        canister_id = %memory,
         embedding_len = embedding.len(),
         tag = %args.tag,
         "insert-raw prepared embedding"
     );
 
-    client.insert(embedding, &payload).await?;
+    dim_cache::seed(&client, &memory);
+    client
+        .insert_embedding(&memory, &args.tag, &args.text, embedding)
+        .await?;
+    dim_cache::persist(&client, &memory);
     Ok(())
 }
 
-async fn build_memory_client(id: &str, ctx: &CommandContext) -> Result<MemoryClient> {
-    let agent = ctx.agent_factory.build().await?;
-    let memory =
-        Principal::from_text(id).context("Failed to parse canister id for insert-raw command")?;
-    Ok(MemoryClient::new(agent, memory))
-}
-
 fn parse_embedding(raw: &str) -> Result<Vec<f32>> {
-    let parsed: Vec<f32> = serde_json::from_str(raw)
-        .with_context(|| "Embedding must be a JSON array of floats, e.g. [0.1, 0.2]")?;
+    let parsed: Vec<f32> = serde_json::from_str(raw).map_err(|_| {
+        KinicError::Validation(
+            "Embedding must be a JSON array of floats, e.g. [0.1, 0.2]".to_string(),
+        )
+    })?;
     if parsed.is_empty() {
-        bail!("Embedding array cannot be empty");
+        bail!(KinicError::Validation(
+            "Embedding array cannot be empty".to_string()
+        ));
     }
     Ok(parsed)
 }
-
-fn format_chunk_text(tag: &str, sentence: &str) -> String {
-    json!({ "tag": tag, "sentence": sentence }).to_string()
-}
@@ -1,43 +1,142 @@
 use anyhow::Result;
-use ic_agent::export::Principal;
-use tracing::info;
+use tracing::{debug, info, warn};
 
 use crate::{
-    cli::ListArgs,
-    clients::launcher::{LauncherClient, State},
+    cli::{ListArgs, MemoryState},
+    clients::{launcher::State, memory::MemoryMetadata},
+    error::is_method_not_found,
+    kinic_client::KinicClient,
 };
 
-use super::CommandContext;
+use super::{CommandContext, dim_cache};
 
-pub async fn handle(_args: ListArgs, ctx: &CommandContext) -> Result<()> {
-    let agent = ctx.agent_factory.build().await?;
-    let client = LauncherClient::new(agent);
+pub async fn handle(args: ListArgs, ctx: &CommandContext) -> Result<()> {
+    let client = KinicClient::connect(&ctx.agent_factory).await?;
     let states = client.list_memories().await?;
 
-    let principals: Vec<Principal> = states
-        .iter()
-        .filter_map(memory_principal)
-        .cloned()
+    let states: Vec<State> = states
+        .into_iter()
+        .filter(|state| match (memory_state(state), args.state) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(current), Some(wanted)) => current == wanted,
+        })
         .collect();
 
-    if principals.is_empty() {
+    if states.is_empty() {
         println!("No memories found.");
     } else {
         println!("Memories:");
-        for principal in principals {
-            println!("- {principal}");
+        for state in &states {
+            print_memory(&client, state).await;
         }
     }
 
-    info!("listed memories");
+    info!(count = states.len(), "listed memories");
     Ok(())
 }
 
-fn memory_principal(state: &State) -> Option<&Principal> {
-    match state {
-        State::Installation(principal, _)
-        | State::SettingUp(principal)
-        | State::Running(principal) => Some(principal),
+async fn print_memory(client: &KinicClient, state: &State) {
+    let id = state
+        .principal()
+        .map(|principal| principal.to_text())
+        .unwrap_or_else(|| "(canister not created yet)".to_string());
+    println!("- {id} [{}]", state.label());
+
+    let details = memory_details(client, state).await;
+    if let (Some(principal), Some(dim)) = (
+        state.principal(),
+        details.metadata.as_ref().and_then(|m| m.dim),
+    ) {
+        dim_cache::remember_dim(principal, dim as usize);
+    }
+
+    println!("    name: {}", details.name.as_deref().unwrap_or("-"));
+    println!(
+        "    description: {}",
+        details.description.as_deref().unwrap_or("-")
+    );
+    if state.deploy_metadata().is_none()
+        && let Some(message) = state.detail().filter(|text| !text.is_empty())
+    {
+        println!("    status: {message}");
+    }
+    if let Some(metadata) = &details.metadata {
+        println!("    {}", format_stats(metadata));
+    } else if details.stats_unavailable {
+        println!("    dim: n/a | records: n/a | cycles: n/a");
+    }
+}
+
+/// Name, description and stats of one memory, as shown by `list` and `serve`'s `/memories`.
+pub(crate) struct MemoryDetails {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// Reported by running memories whose canister has `get_metadata`.
+    pub metadata: Option<MemoryMetadata>,
+    /// The memory has `get_metadata` but the call failed.
+    pub stats_unavailable: bool,
+}
+
+/// Prefers what a running memory reports about itself, falling back to the name and
+/// description the launcher recorded at deploy time.
+pub(crate) async fn memory_details(client: &KinicClient, state: &State) -> MemoryDetails {
+    let mut stats_unavailable = false;
+    let metadata = match (state, state.principal()) {
+        (State::Running(_), Some(principal)) => match client.metadata(principal).await {
+            Ok(metadata) => Some(metadata),
+            Err(err) if is_method_not_found(&err) => {
+                debug!(canister_id = %principal, "memory canister does not report metadata");
+                None
+            }
+            Err(err) => {
+                warn!(canister_id = %principal, error = %format!("{err:#}"), "failed to read memory metadata");
+                stats_unavailable = true;
+                None
+            }
+        },
         _ => None,
+    };
+
+    let deploy = state.deploy_metadata();
+    MemoryDetails {
+        name: metadata
+            .as_ref()
+            .and_then(|m| m.name.clone())
+            .or_else(|| deploy.as_ref().map(|d| d.name.clone())),
+        description: metadata
+            .as_ref()
+            .and_then(|m| m.description.clone())
+            .or_else(|| deploy.as_ref().map(|d| d.description.clone())),
+        metadata,
+        stats_unavailable,
+    }
+}
+
+fn format_stats(metadata: &MemoryMetadata) -> String {
+    let dim = metadata
+        .dim
+        .map(|dim| dim.to_string())
+        .unwrap_or_else(|| "-".to_string());
+    let count = metadata
+        .count
+        .map(|count| count.to_string())
+        .unwrap_or_else(|| "-".to_string());
+    let cycles = metadata
+        .cycles
+        .as_ref()
+        .map(|cycles| cycles.to_string())
+        .unwrap_or_else(|| "-".to_string());
+    format!("dim: {dim} | records: {count} | cycles: {cycles}")
+}
+
+fn memory_state(state: &State) -> Option<MemoryState> {
+    match state {
+        State::Empty(_) => None,
+        State::Pending(_) => Some(MemoryState::Pending),
+        State::Creation(_) => Some(MemoryState::Creation),
+        State::Installation(_, _) => Some(MemoryState::Installation),
+        State::SettingUp(_) => Some(MemoryState::SettingUp),
+        State::Running(_) => Some(MemoryState::Running),
     }
 }
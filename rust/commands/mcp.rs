@@ -0,0 +1,377 @@
+//! rust/commands/mcp.rs
+//! Where: `mcp` command of the Kinic CLI.
+//! What: Minimal Model Context Protocol server (JSON-RPC 2.0 over stdio) exposing memory tools.
+//! Why: Lets MCP-speaking coding assistants search and write team memory directly.
+
+use anyhow::{Context, Result, anyhow, bail};
+use ic_agent::export::Principal;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tracing::{debug, info};
+
+use crate::{cli::McpArgs, kinic_client::KinicClient};
+
+use super::CommandContext;
+
+/// MCP revisions this server implements, newest first. The 2025 revisions require JSON-RPC
+/// batching and other features that are not implemented here.
+const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2024-11-05"];
+const DEFAULT_TOP_K: usize = 5;
+
+// JSON-RPC 2.0 error codes.
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+struct McpServer {
+    client: KinicClient,
+    default_memory: Option<Principal>,
+}
+
+#[derive(Deserialize)]
+struct RpcRequest {
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+pub async fn handle(args: McpArgs, ctx: &CommandContext) -> Result<()> {
+    let default_memory = args
+        .memory_id
+        .as_deref()
+        .map(Principal::from_text)
+        .transpose()
+        .context("Failed to parse --memory-id")?;
+    let server = McpServer {
+        client: KinicClient::connect(&ctx.agent_factory).await?,
+        default_memory,
+    };
+    info!(memory_id = ?server.default_memory.map(|p| p.to_text()), "MCP server ready on stdio");
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+    while let Some(line) = lines.next_line().await.context("Failed to read stdin")? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some(response) = server.dispatch(&line).await else {
+            continue;
+        };
+        let mut payload = serde_json::to_vec(&response)?;
+        payload.push(b'\n');
+        stdout.write_all(&payload).await?;
+        stdout.flush().await?;
+    }
+    Ok(())
+}
+
+impl McpServer {
+    /// Handles one JSON-RPC message; notifications (no `id`) get no response.
+    async fn dispatch(&self, line: &str) -> Option<Value> {
+        let request: RpcRequest = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(err) => {
+                return Some(rpc_error(Value::Null, PARSE_ERROR, &err.to_string()));
+            }
+        };
+        debug!(method = %request.method, "MCP request");
+        let id = request.id?;
+
+        let result = match request.method.as_str() {
+            "initialize" => Ok(json!({
+                "protocolVersion": negotiate_protocol_version(
+                    request.params.get("protocolVersion").and_then(Value::as_str),
+                ),
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "kinic-cli", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+            "tools/call" => self.call_tool(&request.params).await,
+            other => {
+                return Some(rpc_error(
+                    id,
+                    METHOD_NOT_FOUND,
+                    &format!("method not found: {other}"),
+                ));
+            }
+        };
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(err) => rpc_error(id, INVALID_PARAMS, &format!("{err:#}")),
+        })
+    }
+
+    /// Runs a tool. Unknown tools are protocol errors; tool failures are reported in the result
+    /// with `isError` so the agent can read them.
+    async fn call_tool(&self, params: &Value) -> Result<Value> {
+        let name = params
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("missing tool name"))?;
+        let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+        let outcome = match name {
+            "search_memory" => self.search_memory(&arguments).await,
+            "insert_memory" => self.insert_memory(&arguments).await,
+            "ask_memory" => self.ask_memory(&arguments).await,
+            "list_memories" => self.list_memories().await,
+            other => bail!("unknown tool: {other}"),
+        };
+        info!(tool = name, ok = outcome.is_ok(), "MCP tool call");
+        Ok(match outcome {
+            Ok(text) => json!({ "content": [{ "type": "text", "text": text }] }),
+            Err(err) => json!({
+                "content": [{ "type": "text", "text": format!("{err:#}") }],
+                "isError": true,
+            }),
+        })
+    }
+
+    async fn search_memory(&self, arguments: &Value) -> Result<String> {
+        let memory = self.memory_id(arguments)?;
+        let query = string_arg(arguments, "query")?;
+        let top_k = top_k_arg(arguments);
+        let results = self.client.search(&memory, query).await?;
+        if results.is_empty() {
+            return Ok(format!("No matches found for \"{query}\"."));
+        }
+        Ok(results
+            .into_iter()
+            .take(top_k)
+            .map(|(score, text)| format!("[{score:.4}] {text}"))
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+
+    async fn insert_memory(&self, arguments: &Value) -> Result<String> {
+        let memory = self.memory_id(arguments)?;
+        let text = string_arg(arguments, "text")?;
+        let tag = string_arg(arguments, "tag")?;
+        let count = self.client.insert_text(&memory, tag, text).await?;
+        Ok(format!(
+            "Inserted {count} chunk(s) tagged \"{tag}\" into {memory}."
+        ))
+    }
+
+    async fn ask_memory(&self, arguments: &Value) -> Result<String> {
+        let memory = self.memory_id(arguments)?;
+        let query = string_arg(arguments, "query")?;
+        let language = arguments
+            .get("language")
+            .and_then(Value::as_str)
+            .unwrap_or("en");
+        let result = self
+            .client
+            .ask(&memory, query, top_k_arg(arguments), language)
+            .await?;
+        Ok(result.response)
+    }
+
+    async fn list_memories(&self) -> Result<String> {
+        let states = self.client.list_memories().await?;
+        let lines: Vec<String> = states
+            .iter()
+            .filter_map(|state| {
+                let principal = state.principal()?;
+                let name = state
+                    .deploy_metadata()
+                    .map(|deploy| format!(" {}", deploy.name))
+                    .unwrap_or_default();
+                Some(format!("{principal} [{}]{name}", state.label()))
+            })
+            .collect();
+        if lines.is_empty() {
+            return Ok("No memories found.".to_string());
+        }
+        Ok(lines.join("\n"))
+    }
+
+    fn memory_id(&self, arguments: &Value) -> Result<Principal> {
+        match arguments.get("memory_id").and_then(Value::as_str) {
+            Some(id) => Principal::from_text(id).context("Failed to parse memory_id"),
+            None => self
+                .default_memory
+                .ok_or_else(|| anyhow!("memory_id is required (no --memory-id default set)")),
+        }
+    }
+}
+
+/// Echoes the client's revision when it is supported, otherwise offers the newest one this
+/// server implements and leaves it to the client to disconnect.
+fn negotiate_protocol_version(requested: Option<&str>) -> &'static str {
+    SUPPORTED_PROTOCOL_VERSIONS
+        .iter()
+        .find(|version| Some(**version) == requested)
+        .unwrap_or(&SUPPORTED_PROTOCOL_VERSIONS[0])
+}
+
+fn string_arg<'a>(arguments: &'a Value, name: &str) -> Result<&'a str> {
+    arguments
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("missing string argument `{name}`"))
+}
+
+fn top_k_arg(arguments: &Value) -> usize {
+    arguments
+        .get("top_k")
+        .and_then(Value::as_u64)
+        .map(|top_k| top_k.max(1) as usize)
+        .unwrap_or(DEFAULT_TOP_K)
+}
+
+fn rpc_error(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+fn tool_definitions() -> Value {
+    let memory_id = json!({
+        "type": "string",
+        "description": "Memory canister principal; defaults to the server's --memory-id",
+    });
+    let top_k = json!({
+        "type": "integer",
+        "minimum": 1,
+        "description": "Number of results to use (default 5)",
+    });
+    json!([
+        {
+            "name": "search_memory",
+            "description": "Semantic search over a Kinic memory. Returns scored entries, best first.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Text to search for" },
+                    "top_k": top_k,
+                    "memory_id": memory_id,
+                },
+                "required": ["query"],
+            },
+        },
+        {
+            "name": "insert_memory",
+            "description": "Chunk, embed and store text in a Kinic memory under a tag.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "text": { "type": "string", "description": "Text to store" },
+                    "tag": { "type": "string", "description": "Tag to file the text under" },
+                    "memory_id": memory_id,
+                },
+                "required": ["text", "tag"],
+            },
+        },
+        {
+            "name": "ask_memory",
+            "description": "Answer a question using the most relevant entries of a Kinic memory.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Question to answer" },
+                    "top_k": top_k,
+                    "language": { "type": "string", "description": "Answer language code, e.g. en or ja" },
+                    "memory_id": memory_id,
+                },
+                "required": ["query"],
+            },
+        },
+        {
+            "name": "list_memories",
+            "description": "List the memories deployed by the current identity.",
+            "inputSchema": { "type": "object", "properties": {} },
+        },
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use ic_agent::Agent;
+
+    use super::*;
+
+    fn server() -> McpServer {
+        let agent = Agent::builder()
+            .with_url("http://127.0.0.1:9")
+            .build()
+            .unwrap();
+        McpServer {
+            client: KinicClient::new(agent),
+            default_memory: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn initialize_replies_with_a_supported_protocol_version() {
+        let server = server();
+        let supported = server
+            .dispatch(r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2024-11-05"}}"#)
+            .await
+            .unwrap();
+        assert_eq!(supported["id"], 1);
+        assert_eq!(supported["result"]["protocolVersion"], "2024-11-05");
+        assert_eq!(supported["result"]["serverInfo"]["name"], "kinic-cli");
+
+        let newer = server
+            .dispatch(r#"{"jsonrpc":"2.0","id":2,"method":"initialize","params":{"protocolVersion":"2999-01-01"}}"#)
+            .await
+            .unwrap();
+        assert_eq!(
+            newer["result"]["protocolVersion"],
+            SUPPORTED_PROTOCOL_VERSIONS[0]
+        );
+    }
+
+    #[tokio::test]
+    async fn tools_list_names_every_tool() {
+        let response = server()
+            .dispatch(r#"{"jsonrpc":"2.0","id":"a","method":"tools/list"}"#)
+            .await
+            .unwrap();
+        let names: Vec<&str> = response["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "search_memory",
+                "insert_memory",
+                "ask_memory",
+                "list_memories"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn notifications_get_no_response() {
+        let response = server()
+            .dispatch(r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#)
+            .await;
+        assert!(response.is_none());
+    }
+
+    #[tokio::test]
+    async fn malformed_json_is_a_parse_error() {
+        let response = server().dispatch("{not json").await.unwrap();
+        assert_eq!(response["error"]["code"], PARSE_ERROR);
+        assert_eq!(response["id"], Value::Null);
+    }
+
+    #[tokio::test]
+    async fn unknown_methods_are_reported() {
+        let response = server()
+            .dispatch(r#"{"jsonrpc":"2.0","id":7,"method":"resources/list"}"#)
+            .await
+            .unwrap();
+        assert_eq!(response["id"], 7);
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+    }
+}
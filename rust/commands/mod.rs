@@ -1,27 +1,41 @@
 use anyhow::Result;
 
+use icrc_ledger_types::icrc1::account::Subaccount;
+
 use crate::{agent::AgentFactory, cli::Command};
 
+pub mod acl;
+pub mod allowance;
 pub mod ask_ai;
 pub mod balance;
 pub mod config;
 pub mod convert_pdf;
 pub mod create;
+mod dim_cache;
+pub mod history;
 pub mod ii_login;
 pub mod insert;
 pub mod insert_raw;
 pub mod insert_pdf;
 pub mod list;
+pub mod mcp;
+pub mod prompt;
 pub mod search;
 pub mod search_raw;
+pub mod serve;
+mod serve_openai;
+pub mod sessions;
 pub mod tagged_embeddings;
+pub mod transfer;
 pub mod update;
 pub mod reset;
+pub mod whoami;
 
 #[derive(Clone)]
 pub struct CommandContext {
     pub agent_factory: AgentFactory,
     pub identity_path: Option<std::path::PathBuf>,
+    pub subaccount: Option<Subaccount>,
 }
 
 pub async fn run_command(command: Command, ctx: CommandContext) -> Result<()> {
@@ -36,10 +50,32 @@ pub async fn run_command(command: Command, ctx: CommandContext) -> Result<()> {
         Command::TaggedEmbeddings(args) => tagged_embeddings::handle(args, &ctx).await,
         Command::ConvertPdf(args) => convert_pdf::handle(args).await,
         Command::Config(args) => config::handle(args, &ctx).await,
+        Command::Acl(args) => acl::handle(args, &ctx).await,
         Command::Update(args) => update::handle(args, &ctx).await,
         Command::Reset(args) => reset::handle(args, &ctx).await,
         Command::Balance(args) => balance::handle(args, &ctx).await,
+        Command::Transfer(args) => transfer::handle(args, &ctx).await,
+        Command::Allowance(args) => allowance::handle(args, &ctx).await,
+        Command::History(args) => history::handle(args, &ctx).await,
         Command::AskAi(args) => ask_ai::handle(args, &ctx).await,
         Command::Login(args) => ii_login::handle(args, &ctx).await,
+        Command::Whoami(args) => whoami::handle(args, &ctx).await,
+        Command::Logout(args) => sessions::logout(args, &ctx).await,
+        Command::Sessions(args) => sessions::handle(args, &ctx).await,
+        Command::Serve(args) => serve::handle(args, &ctx).await,
+        Command::Mcp(args) => mcp::handle(args, &ctx).await,
+    }
+}
+
+/// Renders a remaining duration (in nanoseconds) as a short countdown such as `5h 12m`.
+pub(crate) fn format_remaining(nanos: u64) -> String {
+    let secs = nanos / 1_000_000_000;
+    let (hours, minutes, seconds) = (secs / 3_600, (secs % 3_600) / 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds}s")
+    } else {
+        format!("{seconds}s")
     }
 }
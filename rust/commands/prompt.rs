@@ -0,0 +1,29 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use anyhow::{Context, Result, bail};
+
+use crate::error::KinicError;
+
+/// Asks a yes/no question on the terminal. `assume_yes` short-circuits the prompt, and
+/// non-interactive sessions must opt in explicitly instead of blocking on stdin.
+pub fn confirm(question: &str, assume_yes: bool) -> Result<bool> {
+    if assume_yes {
+        return Ok(true);
+    }
+    if !io::stdin().is_terminal() {
+        bail!(KinicError::Validation(
+            "Confirmation required but stdin is not a terminal; re-run with --yes to proceed"
+                .to_string()
+        ));
+    }
+
+    print!("{question} [y/N]: ");
+    io::stdout().flush().context("Failed to flush stdout")?;
+
+    let mut answer = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut answer)
+        .context("Failed to read confirmation from stdin")?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
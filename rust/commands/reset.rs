@@ -1,18 +1,73 @@
-use anyhow::{Context, Result};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::{Context, Result, bail};
 use ic_agent::export::Principal;
-use tracing::info;
+use serde::Serialize;
+use tracing::{debug, info};
 
-use crate::{cli::ResetArgs, clients::memory::MemoryClient};
+use crate::{
+    cli::ResetArgs, clients::memory::MemoryMetadata, embedding::embedding_dimension,
+    error::KinicError, kinic_client::KinicClient,
+};
 
-use super::CommandContext;
+use super::{CommandContext, dim_cache, prompt::confirm};
 
 pub async fn handle(args: ResetArgs, ctx: &CommandContext) -> Result<()> {
-    let client = build_memory_client(&args.memory_id, ctx).await?;
+    if !args.force {
+        let provider_dim = embedding_dimension()
+            .await
+            .context("Could not verify --dim against the embedding API; pass --force to skip")?;
+        if provider_dim != args.dim {
+            bail!(KinicError::Validation(format!(
+                "--dim {} does not match the embedding API dimension {provider_dim}; inserts and searches would fail. Pass --force to reset anyway",
+                args.dim
+            )));
+        }
+    }
+
+    let client = KinicClient::connect(&ctx.agent_factory).await?;
+    let memory = Principal::from_text(&args.memory_id)
+        .context("Failed to parse canister id for reset command")?;
+    let metadata = client.metadata(&memory).await.unwrap_or_else(|err| {
+        debug!("metadata unavailable for {}: {err:#}", args.memory_id);
+        MemoryMetadata::default()
+    });
+
+    println!("About to reset memory {}:", args.memory_id);
+    println!(
+        "  Name:    {}",
+        metadata.name.as_deref().unwrap_or("(unknown)")
+    );
+    println!(
+        "  Records: {}",
+        metadata
+            .count
+            .map(|count| count.to_string())
+            .unwrap_or_else(|| "(unknown)".to_string())
+    );
+    if let Some(dim) = metadata.dim {
+        println!("  Dim:     {dim} -> {}", args.dim);
+    }
+    println!("All records will be deleted.");
+    if !confirm("Reset this memory?", args.yes)? {
+        println!("Aborted; memory left unchanged.");
+        return Ok(());
+    }
+
+    if let Some(path) = &args.backup_to {
+        let written = backup(&client, &memory, path).await?;
+        println!("Backed up {written} records to {}", path.display());
+    }
 
-    client.reset(args.dim).await?;
+    client.reset(&memory, args.dim).await?;
+    dim_cache::remember_dim(&memory, args.dim);
 
     info!(
-        canister_id = %client.canister_id(),
+        canister_id = %memory,
         dim = args.dim,
         "memory reset completed"
     );
@@ -20,9 +75,30 @@ pub async fn handle(args: ResetArgs, ctx: &CommandContext) -> Result<()> {
     Ok(())
 }
 
-async fn build_memory_client(id: &str, ctx: &CommandContext) -> Result<MemoryClient> {
-    let agent = ctx.agent_factory.build().await?;
-    let memory = Principal::from_text(id)
-        .context("Failed to parse canister id for reset command")?;
-    Ok(MemoryClient::new(agent, memory))
+#[derive(Serialize)]
+struct BackupRecord<'a> {
+    text: &'a str,
+    embedding: &'a [f32],
+}
+
+/// Writes every record as one JSON object per line; any failure aborts the reset.
+async fn backup(client: &KinicClient, memory: &Principal, path: &Path) -> Result<usize> {
+    let records = client
+        .export(memory)
+        .await
+        .context("Failed to export memory (must fit in one ~3 MB query reply); reset aborted")?;
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create backup file {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    for (embedding, text) in &records {
+        serde_json::to_writer(&mut writer, &BackupRecord { text, embedding })
+            .context("Failed to encode backup record")?;
+        writer.write_all(b"\n")?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| e.into_error())
+        .and_then(|file| file.sync_all())
+        .with_context(|| format!("Failed to write backup file {}", path.display()))?;
+    Ok(records.len())
 }
@@ -1,22 +1,21 @@
-use std::cmp::Ordering;
-
 use anyhow::{Context, Result};
 use ic_agent::export::Principal;
 use tracing::info;
 
-use crate::{cli::SearchArgs, clients::memory::MemoryClient, embedding::fetch_embedding};
+use crate::{cli::SearchArgs, kinic_client::KinicClient};
 
-use super::CommandContext;
+use super::{CommandContext, dim_cache};
 
 pub async fn handle(args: SearchArgs, ctx: &CommandContext) -> Result<()> {
-    let client = build_memory_client(&args.memory_id, ctx).await?;
-    let embedding = fetch_embedding(&args.query).await?;
-    let mut results = client.search(embedding).await?;
-
-    results.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+    let client = KinicClient::connect(&ctx.agent_factory).await?;
+    let memory = Principal::from_text(&args.memory_id)
+        .context("Failed to parse canister id for search command")?;
+    dim_cache::seed(&client, &memory);
+    let results = client.search(&memory, &args.query).await?;
+    dim_cache::persist(&client, &memory);
 
     info!(
-        canister_id = %client.canister_id(),
+        canister_id = %memory,
         query = %args.query,
         result_count = results.len(),
         "search completed"
@@ -33,10 +32,3 @@ pub async fn handle(args: SearchArgs, ctx: &CommandContext) -> Result<()> {
 
     Ok(())
 }
-
-async fn build_memory_client(id: &str, ctx: &CommandContext) -> Result<MemoryClient> {
-    let agent = ctx.agent_factory.build().await?;
-    let memory =
-        Principal::from_text(id).context("Failed to parse canister id for search command")?;
-    Ok(MemoryClient::new(agent, memory))
-}
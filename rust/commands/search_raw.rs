@@ -2,19 +2,21 @@ use anyhow::{Context, Result, bail};
 use ic_agent::export::Principal;
 use tracing::info;
 
-use crate::{cli::SearchRawArgs, clients::memory::MemoryClient};
+use crate::{cli::SearchRawArgs, error::KinicError, kinic_client::KinicClient};
 
-use super::CommandContext;
+use super::{CommandContext, dim_cache};
 
 pub async fn handle(args: SearchRawArgs, ctx: &CommandContext) -> Result<()> {
-    let client = build_memory_client(&args.memory_id, ctx).await?;
+    let client = KinicClient::connect(&ctx.agent_factory).await?;
+    let memory = Principal::from_text(&args.memory_id)
+        .context("Failed to parse canister id for search-raw command")?;
     let embedding = parse_embedding(&args.embedding)?;
-    let mut results = client.search(embedding).await?;
-
-    results.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    dim_cache::seed(&client, &memory);
+    let results = client.search_embedding(&memory, embedding).await?;
+    dim_cache::persist(&client, &memory);
 
     info!(
-        canister_id = %client.canister_id(),
+        canister_id = %memory,
         result_count = results.len(),
         "search-raw completed"
     );
@@ -26,18 +28,16 @@ pub async fn handle(args: SearchRawArgs, ctx: &CommandContext) -> Result<()> {
     Ok(())
 }
 
-async fn build_memory_client(id: &str, ctx: &CommandContext) -> Result<MemoryClient> {
-    let agent = ctx.agent_factory.build().await?;
-    let memory =
-        Principal::from_text(id).context("Failed to parse canister id for search-raw command")?;
-    Ok(MemoryClient::new(agent, memory))
-}
-
 fn parse_embedding(raw: &str) -> Result<Vec<f32>> {
-    let parsed: Vec<f32> = serde_json::from_str(raw)
-        .with_context(|| "Embedding must be a JSON array of floats, e.g. [0.1, 0.2]")?;
+    let parsed: Vec<f32> = serde_json::from_str(raw).map_err(|_| {
+        KinicError::Validation(
+            "Embedding must be a JSON array of floats, e.g. [0.1, 0.2]".to_string(),
+        )
+    })?;
     if parsed.is_empty() {
-        bail!("Embedding array cannot be empty");
+        bail!(KinicError::Validation(
+            "Embedding array cannot be empty".to_string()
+        ));
     }
     Ok(parsed)
 }
@@ -0,0 +1,445 @@
+//! rust/commands/serve.rs
+//! Where: `serve` command of the Kinic CLI.
+//! What: Exposes insert, search, ask-ai, list and balance as JSON HTTP endpoints, plus the
+//! OpenAI-style `/v1` routes from `serve_openai`.
+//! Why: Lets other local services use memories without shelling out to the CLI per call.
+
+use std::{net::SocketAddr, sync::Arc, time::Instant};
+
+use anyhow::{Context, Result, bail};
+use axum::{
+    Json, Router,
+    extract::{Path, Request, State},
+    http::{StatusCode, header::AUTHORIZATION},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use ic_agent::export::Principal;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+
+use crate::{
+    cli::ServeArgs,
+    clients::launcher::State as LauncherState,
+    error::{ErrorKind, KinicError},
+    kinic_client::KinicClient,
+    ledger::format_kinic,
+};
+
+use super::{CommandContext, list::memory_details, serve_openai};
+
+const TOKEN_ENV: &str = "KINIC_SERVE_TOKEN";
+const DEFAULT_TOP_K: usize = 5;
+
+pub(super) struct ServerState {
+    pub(super) client: KinicClient,
+    token: Option<String>,
+}
+
+pub async fn handle(args: ServeArgs, ctx: &CommandContext) -> Result<()> {
+    let token = args
+        .token
+        .or_else(|| std::env::var(TOKEN_ENV).ok())
+        .filter(|token| !token.is_empty());
+    check_bind(args.bind, token.is_some(), args.insecure_no_auth)?;
+
+    let client = KinicClient::connect(&ctx.agent_factory)
+        .await?
+        .with_subaccount(ctx.subaccount);
+    let state = Arc::new(ServerState { client, token });
+    let app = router(state.clone());
+
+    let listener = TcpListener::bind(args.bind)
+        .await
+        .with_context(|| format!("Failed to bind to {}", args.bind))?;
+    let addr: SocketAddr = listener
+        .local_addr()
+        .context("Failed to read server address")?;
+    info!(%addr, auth = state.token.is_some(), "serving Kinic API");
+    println!("Serving Kinic API on http://{addr} (Ctrl+C to stop)");
+
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+        .context("Kinic API server failed")
+}
+
+/// Refuses a non-loopback address without a token unless `--insecure-no-auth` is given.
+fn check_bind(bind: SocketAddr, has_token: bool, insecure_no_auth: bool) -> Result<()> {
+    if has_token || bind.ip().is_loopback() {
+        return Ok(());
+    }
+    if !insecure_no_auth {
+        bail!(KinicError::Validation(format!(
+            "Refusing to serve on {bind} without a bearer token: anyone who can reach it could use this identity. Set --token or {TOKEN_ENV}, or pass --insecure-no-auth"
+        )));
+    }
+    warn!(
+        %bind,
+        "serving on a non-loopback address without a bearer token; anyone who can reach it can use this identity"
+    );
+    Ok(())
+}
+
+fn router(state: Arc<ServerState>) -> Router {
+    Router::new()
+        .route("/health", get(health_handler))
+        .route("/memories", get(list_handler))
+        .route("/memories/:memory_id/insert", post(insert_handler))
+        .route("/memories/:memory_id/search", post(search_handler))
+        .route("/memories/:memory_id/ask-ai", post(ask_ai_handler))
+        .route("/balance", get(balance_handler))
+        .merge(serve_openai::routes())
+        .layer(middleware::from_fn_with_state(state.clone(), require_token))
+        .layer(middleware::from_fn(log_request))
+        .with_state(state)
+}
+
+/// Error returned by handlers, rendered as `{"error": "..."}` with a matching status code.
+pub(super) struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    pub(super) fn bad_request(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(err: anyhow::Error) -> Self {
+        let status = match ErrorKind::of(&err) {
+            ErrorKind::Validation => StatusCode::BAD_REQUEST,
+            ErrorKind::Auth => StatusCode::UNAUTHORIZED,
+            ErrorKind::Network | ErrorKind::CanisterReject | ErrorKind::Embedding => {
+                StatusCode::BAD_GATEWAY
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self {
+            status,
+            message: format!("{err:#}"),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(json!({ "error": self.message }))).into_response()
+    }
+}
+
+pub(super) type ApiResult<T> = std::result::Result<Json<T>, ApiError>;
+
+async fn require_token(
+    State(state): State<Arc<ServerState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(expected) = &state.token else {
+        return next.run(request).await;
+    };
+    let provided = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let authorized = provided.is_some_and(|provided| tokens_match(provided, expected));
+    if !authorized {
+        return ApiError {
+            status: StatusCode::UNAUTHORIZED,
+            message: "missing or invalid bearer token".to_string(),
+        }
+        .into_response();
+    }
+    next.run(request).await
+}
+
+/// Compares SHA-256 digests so the check does not leak how much of the token matched.
+fn tokens_match(provided: &str, expected: &str) -> bool {
+    let digest = |value: &str| ring::digest::digest(&ring::digest::SHA256, value.as_bytes());
+    digest(provided).as_ref() == digest(expected).as_ref()
+}
+
+async fn log_request(request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let started = Instant::now();
+    let response = next.run(request).await;
+    info!(
+        %method,
+        %path,
+        status = response.status().as_u16(),
+        elapsed_ms = started.elapsed().as_millis() as u64,
+        "handled request"
+    );
+    response
+}
+
+async fn health_handler() -> Json<serde_json::Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+#[derive(Serialize)]
+struct MemorySummary {
+    id: Option<String>,
+    state: &'static str,
+    name: Option<String>,
+    description: Option<String>,
+}
+
+async fn list_handler(State(state): State<Arc<ServerState>>) -> ApiResult<Vec<MemorySummary>> {
+    let mut memories = Vec::new();
+    let launched = state.client.list_memories().await?.into_iter();
+    for memory in launched.filter(|memory| !matches!(memory, LauncherState::Empty(_))) {
+        let details = memory_details(&state.client, &memory).await;
+        memories.push(MemorySummary {
+            id: memory.principal().map(Principal::to_text),
+            state: memory.label(),
+            name: details.name,
+            description: details.description,
+        });
+    }
+    Ok(Json(memories))
+}
+
+#[derive(Deserialize)]
+struct InsertRequest {
+    tag: String,
+    text: String,
+    /// Precomputed embedding; when set, `text` is stored as-is instead of being chunked.
+    embedding: Option<Vec<f32>>,
+}
+
+async fn insert_handler(
+    State(state): State<Arc<ServerState>>,
+    Path(memory_id): Path<String>,
+    Json(request): Json<InsertRequest>,
+) -> ApiResult<serde_json::Value> {
+    let memory = parse_memory_id(&memory_id)?;
+    let inserted = match request.embedding {
+        Some(embedding) => {
+            state
+                .client
+                .insert_embedding(&memory, &request.tag, &request.text, embedding)
+                .await?;
+            1
+        }
+        None => {
+            state
+                .client
+                .insert_text(&memory, &request.tag, &request.text)
+                .await?
+        }
+    };
+    Ok(Json(json!({ "inserted": inserted })))
+}
+
+#[derive(Deserialize)]
+struct SearchRequest {
+    query: Option<String>,
+    embedding: Option<Vec<f32>>,
+}
+
+#[derive(Serialize)]
+struct SearchHit {
+    score: f32,
+    text: String,
+}
+
+async fn search_handler(
+    State(state): State<Arc<ServerState>>,
+    Path(memory_id): Path<String>,
+    Json(request): Json<SearchRequest>,
+) -> ApiResult<Vec<SearchHit>> {
+    let memory = parse_memory_id(&memory_id)?;
+    let results = match (request.query, request.embedding) {
+        (Some(query), None) => state.client.search(&memory, &query).await?,
+        (None, Some(embedding)) => state.client.search_embedding(&memory, embedding).await?,
+        _ => {
+            return Err(ApiError::bad_request(
+                "exactly one of `query` or `embedding` must be provided",
+            ));
+        }
+    };
+    Ok(Json(
+        results
+            .into_iter()
+            .map(|(score, text)| SearchHit { score, text })
+            .collect(),
+    ))
+}
+
+#[derive(Deserialize)]
+struct AskAiRequest {
+    query: String,
+    top_k: Option<usize>,
+    language: Option<String>,
+}
+
+async fn ask_ai_handler(
+    State(state): State<Arc<ServerState>>,
+    Path(memory_id): Path<String>,
+    Json(request): Json<AskAiRequest>,
+) -> ApiResult<serde_json::Value> {
+    let memory = parse_memory_id(&memory_id)?;
+    let result = state
+        .client
+        .ask(
+            &memory,
+            &request.query,
+            request.top_k.unwrap_or(DEFAULT_TOP_K),
+            request.language.as_deref().unwrap_or("en"),
+        )
+        .await?;
+    Ok(Json(json!({
+        "prompt": result.prompt,
+        "response": result.response,
+        "context_count": result.context_count,
+    })))
+}
+
+async fn balance_handler(State(state): State<Arc<ServerState>>) -> ApiResult<serde_json::Value> {
+    let account = state.client.account()?;
+    let balance = state.client.balance().await?;
+    Ok(Json(json!({
+        "account": account.to_string(),
+        "e8s": balance.to_string(),
+        "kinic": format_kinic(balance),
+    })))
+}
+
+pub(super) fn parse_memory_id(memory_id: &str) -> Result<Principal, ApiError> {
+    Principal::from_text(memory_id)
+        .map_err(|_| ApiError::bad_request(format!("invalid memory id: {memory_id}")))
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use ic_agent::Agent;
+    use serde_json::Value;
+
+    use super::*;
+
+    /// Serves the router on an ephemeral port. The agent points at a closed port, so only
+    /// routes that fail before reaching a canister are exercised.
+    pub(in crate::commands) async fn spawn(token: Option<&str>) -> String {
+        let agent = Agent::builder()
+            .with_url("http://127.0.0.1:9")
+            .build()
+            .unwrap();
+        let state = Arc::new(ServerState {
+            client: KinicClient::new(agent),
+            token: token.map(str::to_string),
+        });
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router(state)).await });
+        format!("http://{addr}")
+    }
+
+    pub(in crate::commands) fn http() -> reqwest::Client {
+        reqwest::Client::builder().no_proxy().build().unwrap()
+    }
+
+    #[tokio::test]
+    async fn token_is_required_when_configured() {
+        let base = spawn(Some("secret")).await;
+
+        let missing = http().get(format!("{base}/health")).send().await.unwrap();
+        assert_eq!(missing.status(), StatusCode::UNAUTHORIZED);
+        let body: Value = missing.json().await.unwrap();
+        assert_eq!(body["error"], "missing or invalid bearer token");
+
+        let wrong = http()
+            .get(format!("{base}/health"))
+            .bearer_auth("nope")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(wrong.status(), StatusCode::UNAUTHORIZED);
+
+        let ok = http()
+            .get(format!("{base}/health"))
+            .bearer_auth("secret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(ok.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn no_token_means_open_access() {
+        let base = spawn(None).await;
+        let response = http().get(format!("{base}/health")).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["status"], "ok");
+    }
+
+    #[tokio::test]
+    async fn handler_errors_are_json() {
+        let base = spawn(None).await;
+
+        let bad_id = http()
+            .post(format!("{base}/memories/not-a-principal/search"))
+            .json(&json!({ "query": "hello" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(bad_id.status(), StatusCode::BAD_REQUEST);
+        let body: Value = bad_id.json().await.unwrap();
+        assert_eq!(body["error"], "invalid memory id: not-a-principal");
+
+        let both = http()
+            .post(format!("{base}/memories/aaaaa-aa/search"))
+            .json(&json!({ "query": "hello", "embedding": [0.5] }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(both.status(), StatusCode::BAD_REQUEST);
+        let body: Value = both.json().await.unwrap();
+        assert!(body["error"].as_str().unwrap().contains("exactly one"));
+    }
+
+    #[test]
+    fn error_kinds_map_to_statuses() {
+        let status = |err: anyhow::Error| ApiError::from(err).status;
+        assert_eq!(
+            status(KinicError::Validation("bad".into()).into()),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            status(anyhow::Error::from(KinicError::Auth("expired".into())).context("loading")),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(KinicError::Network("down".into()).into()),
+            StatusCode::BAD_GATEWAY
+        );
+        assert_eq!(
+            status(anyhow::anyhow!("unexpected")),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[test]
+    fn non_loopback_bind_needs_a_token_or_opt_out() {
+        let public: SocketAddr = "0.0.0.0:8080".parse().unwrap();
+        let local: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+        assert!(check_bind(local, false, false).is_ok());
+        assert!(check_bind(public, true, false).is_ok());
+        assert!(check_bind(public, false, true).is_ok());
+        let err = check_bind(public, false, false).unwrap_err();
+        assert_eq!(ErrorKind::of(&err), ErrorKind::Validation);
+    }
+}
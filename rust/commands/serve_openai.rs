@@ -0,0 +1,294 @@
+//! rust/commands/serve_openai.rs
+//! Where: `/v1` routes of the `serve` command.
+//! What: OpenAI-style embeddings plus a retrieve/upsert vector store API over one memory.
+//! Why: Lets RAG frameworks (LangChain, LlamaIndex, ...) use a Kinic memory as a vector store.
+
+use std::sync::Arc;
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    routing::post,
+};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{embedding::fetch_embedding, kinic_client::split_tagged_text};
+
+use super::serve::{ApiError, ApiResult, ServerState, parse_memory_id};
+
+const DEFAULT_MODEL: &str = "kinic-embedding";
+const DEFAULT_TAG: &str = "rag";
+const DEFAULT_TOP_K: usize = 4;
+
+pub(super) fn routes() -> Router<Arc<ServerState>> {
+    Router::new()
+        .route("/v1/embeddings", post(embeddings_handler))
+        .route("/v1/memories/:memory_id/retrieve", post(retrieve_handler))
+        .route("/v1/memories/:memory_id/documents", post(upsert_handler))
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EmbeddingInput {
+    Single(String),
+    Batch(Vec<String>),
+}
+
+#[derive(Deserialize)]
+struct EmbeddingsRequest {
+    input: EmbeddingInput,
+    model: Option<String>,
+    /// `float` (default) or `base64`; the official OpenAI clients ask for base64.
+    encoding_format: Option<String>,
+}
+
+async fn embeddings_handler(Json(request): Json<EmbeddingsRequest>) -> ApiResult<Value> {
+    let base64 = match request.encoding_format.as_deref() {
+        None | Some("float") => false,
+        Some("base64") => true,
+        Some(other) => {
+            return Err(ApiError::bad_request(format!(
+                "unsupported encoding_format: {other}"
+            )));
+        }
+    };
+    let inputs = match request.input {
+        EmbeddingInput::Single(text) => vec![text],
+        EmbeddingInput::Batch(texts) => texts,
+    };
+
+    let mut data = Vec::with_capacity(inputs.len());
+    for (index, text) in inputs.iter().enumerate() {
+        let embedding = encode_embedding(&fetch_embedding(text).await?, base64);
+        data.push(json!({ "object": "embedding", "index": index, "embedding": embedding }));
+    }
+    Ok(Json(json!({
+        "object": "list",
+        "data": data,
+        "model": request.model.unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+        // The embedding API does not report token counts.
+        "usage": { "prompt_tokens": 0, "total_tokens": 0 },
+    })))
+}
+
+/// Encodes an embedding as a JSON float array, or as base64 of its little-endian f32 bytes,
+/// which is what the OpenAI clients decode.
+fn encode_embedding(embedding: &[f32], base64: bool) -> Value {
+    if base64 {
+        let bytes: Vec<u8> = embedding.iter().flat_map(|v| v.to_le_bytes()).collect();
+        json!(BASE64_STANDARD.encode(bytes))
+    } else {
+        json!(embedding)
+    }
+}
+
+#[derive(Deserialize)]
+struct RetrieveRequest {
+    query: Option<String>,
+    embedding: Option<Vec<f32>>,
+    top_k: Option<usize>,
+    /// Only return entries stored under this tag.
+    tag: Option<String>,
+}
+
+#[derive(Serialize)]
+struct RetrievedDocument {
+    object: &'static str,
+    index: usize,
+    score: f32,
+    text: String,
+    tag: Option<String>,
+}
+
+async fn retrieve_handler(
+    State(state): State<Arc<ServerState>>,
+    Path(memory_id): Path<String>,
+    Json(request): Json<RetrieveRequest>,
+) -> ApiResult<Value> {
+    let memory = parse_memory_id(&memory_id)?;
+    let results = match (request.query, request.embedding) {
+        (Some(query), None) => state.client.search(&memory, &query).await?,
+        (None, Some(embedding)) => state.client.search_embedding(&memory, embedding).await?,
+        _ => {
+            return Err(ApiError::bad_request(
+                "exactly one of `query` or `embedding` must be provided",
+            ));
+        }
+    };
+
+    // The canister has no top-k or tag parameter, so the tag filter only sees the candidates
+    // its search returns; `candidates` tells callers how many that was.
+    let candidates = results.len();
+    let data = select_documents(
+        results,
+        request.tag.as_deref(),
+        request.top_k.unwrap_or(DEFAULT_TOP_K),
+    );
+    Ok(Json(json!({
+        "object": "list",
+        "data": data,
+        "candidates": candidates,
+    })))
+}
+
+/// Keeps the best `top_k` (at least one) results stored under `tag`, if given.
+fn select_documents(
+    results: Vec<(f32, String)>,
+    tag: Option<&str>,
+    top_k: usize,
+) -> Vec<RetrievedDocument> {
+    results
+        .into_iter()
+        .map(|(score, stored)| {
+            let (stored_tag, text) = split_tagged_text(stored);
+            (score, stored_tag, text)
+        })
+        .filter(|(_, stored_tag, _)| tag.is_none() || stored_tag.as_deref() == tag)
+        .take(top_k.max(1))
+        .enumerate()
+        .map(|(index, (score, tag, text))| RetrievedDocument {
+            object: "retrieval_result",
+            index,
+            score,
+            text,
+            tag,
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct UpsertDocument {
+    text: String,
+    tag: Option<String>,
+    embedding: Option<Vec<f32>>,
+}
+
+#[derive(Deserialize)]
+struct UpsertRequest {
+    documents: Vec<UpsertDocument>,
+}
+
+/// Stores each document as one entry; RAG frameworks chunk before calling this.
+async fn upsert_handler(
+    State(state): State<Arc<ServerState>>,
+    Path(memory_id): Path<String>,
+    Json(request): Json<UpsertRequest>,
+) -> ApiResult<Value> {
+    let memory = parse_memory_id(&memory_id)?;
+    let inserted = request.documents.len();
+    for document in request.documents {
+        let embedding = match document.embedding {
+            Some(embedding) => embedding,
+            None => fetch_embedding(&document.text).await?,
+        };
+        let tag = document.tag.as_deref().unwrap_or(DEFAULT_TAG);
+        state
+            .client
+            .insert_embedding(&memory, tag, &document.text, embedding)
+            .await?;
+    }
+    Ok(Json(json!({ "object": "list", "inserted": inserted })))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+
+    use super::*;
+    use crate::commands::serve::tests::{http, spawn};
+    use crate::kinic_client::tagged_text;
+
+    #[test]
+    fn float_encoding_is_a_json_array() {
+        assert_eq!(encode_embedding(&[0.5, -1.0], false), json!([0.5, -1.0]));
+    }
+
+    #[test]
+    fn base64_encoding_is_little_endian_f32() {
+        let encoded = encode_embedding(&[1.0, -2.5], true);
+        let bytes = BASE64_STANDARD.decode(encoded.as_str().unwrap()).unwrap();
+        assert_eq!(bytes, [0, 0, 128, 63, 0, 0, 32, 192]);
+        let decoded: Vec<f32> = bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+        assert_eq!(decoded, vec![1.0, -2.5]);
+    }
+
+    #[test]
+    fn tag_filter_keeps_matching_entries_up_to_top_k() {
+        let results = vec![
+            (0.9, tagged_text("notes", "first")),
+            (0.8, tagged_text("rag", "second")),
+            (0.7, "untagged".to_string()),
+            (0.6, tagged_text("rag", "third")),
+            (0.5, tagged_text("rag", "fourth")),
+        ];
+
+        let tagged = select_documents(results.clone(), Some("rag"), 2);
+        let texts: Vec<&str> = tagged.iter().map(|doc| doc.text.as_str()).collect();
+        assert_eq!(texts, vec!["second", "third"]);
+        assert_eq!(tagged[1].index, 1);
+        assert_eq!(tagged[1].tag.as_deref(), Some("rag"));
+
+        let all = select_documents(results, None, 0);
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].text, "first");
+    }
+
+    #[tokio::test]
+    async fn embeddings_reject_unknown_encoding() {
+        let base = spawn(None).await;
+        let response = http()
+            .post(format!("{base}/v1/embeddings"))
+            .json(&json!({ "input": "hello", "encoding_format": "int8" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["error"], "unsupported encoding_format: int8");
+    }
+
+    #[tokio::test]
+    async fn retrieve_needs_exactly_one_of_query_or_embedding() {
+        let base = spawn(None).await;
+        let url = format!("{base}/v1/memories/aaaaa-aa/retrieve");
+        for body in [json!({}), json!({ "query": "q", "embedding": [0.1] })] {
+            let response = http().post(&url).json(&body).send().await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+
+        let response = http()
+            .post(format!("{base}/v1/memories/bad-id/retrieve"))
+            .json(&json!({ "query": "q" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn upsert_validates_the_memory_and_counts_documents() {
+        let base = spawn(None).await;
+        let response = http()
+            .post(format!("{base}/v1/memories/bad-id/documents"))
+            .json(&json!({ "documents": [{ "text": "t" }] }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = http()
+            .post(format!("{base}/v1/memories/aaaaa-aa/documents"))
+            .json(&json!({ "documents": [] }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["inserted"], 0);
+    }
+}
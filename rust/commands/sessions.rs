@@ -0,0 +1,133 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+
+use crate::{
+    cli::{LogoutArgs, SessionsArgs, SessionsCommand},
+    identity_store::{
+        default_identity_path, derive_principal_from_user_key, forget_protected_key,
+        protect_session_key, read_stored_identity, remaining_validity_ns, remove_identity,
+        save_identity, session_key_pkcs8, sessions_dir,
+    },
+};
+
+use super::{CommandContext, format_remaining, ii_login::protection_mode};
+
+pub async fn handle(args: SessionsArgs, ctx: &CommandContext) -> Result<()> {
+    match args.command {
+        SessionsCommand::List => list(ctx),
+        SessionsCommand::Protect(protect) => {
+            let path = ctx
+                .identity_path
+                .clone()
+                .context("Identity path is missing")?;
+            let mut stored = read_stored_identity(&path)?;
+            let pkcs8 = session_key_pkcs8(&stored)?;
+            let previous = stored.protection.clone();
+            protect_session_key(&mut stored, &pkcs8, protection_mode(protect.mode))?;
+            save_identity(&path, &stored)?;
+            // Drop the old keyring entry only once the new file is in place.
+            forget_protected_key(&previous)?;
+            println!(
+                "Session key at {} is now protected with: {}",
+                path.display(),
+                stored.protection.label()
+            );
+            Ok(())
+        }
+    }
+}
+
+pub async fn logout(args: LogoutArgs, ctx: &CommandContext) -> Result<()> {
+    let paths = if args.all {
+        stored_sessions()?
+            .into_iter()
+            .map(|(_, path)| path)
+            .collect()
+    } else {
+        vec![
+            ctx.identity_path
+                .clone()
+                .context("Identity path is missing")?,
+        ]
+    };
+
+    let mut removed = 0;
+    for path in &paths {
+        if remove_identity(path)? {
+            println!("Removed session at {}", path.display());
+            removed += 1;
+        }
+    }
+    if removed == 0 {
+        println!("No stored session to remove.");
+    }
+    Ok(())
+}
+
+fn list(ctx: &CommandContext) -> Result<()> {
+    let sessions = stored_sessions()?;
+    if sessions.is_empty() {
+        println!("No stored Internet Identity sessions. Run `kinic-cli --ii login` to create one.");
+        return Ok(());
+    }
+
+    println!("Stored Internet Identity sessions (* = selected):");
+    for (name, path) in sessions {
+        let marker = if ctx.identity_path.as_deref() == Some(path.as_path()) {
+            '*'
+        } else {
+            '-'
+        };
+        println!("{marker} {name}: {}", describe_session(&path));
+    }
+    Ok(())
+}
+
+fn describe_session(path: &Path) -> String {
+    let stored = match read_stored_identity(path) {
+        Ok(stored) => stored,
+        Err(err) => return format!("unreadable ({err:#})"),
+    };
+    let principal = hex::decode(&stored.user_public_key_hex)
+        .ok()
+        .and_then(|key| derive_principal_from_user_key(&key).ok())
+        .map(|principal| principal.to_text())
+        .unwrap_or_else(|| "unknown principal".to_string());
+    let validity = match remaining_validity_ns(&stored) {
+        Ok(0) => "expired".to_string(),
+        Ok(remaining) => format!("expires in {}", format_remaining(remaining)),
+        Err(_) => "unknown expiry".to_string(),
+    };
+    format!(
+        "{principal} ({validity}, key protection: {})",
+        stored.protection.label()
+    )
+}
+
+/// The default `identity.json` plus every `sessions/<name>.json`, in name order.
+fn stored_sessions() -> Result<Vec<(String, std::path::PathBuf)>> {
+    let mut sessions = Vec::new();
+    let default_path = default_identity_path()?;
+    if default_path.exists() {
+        sessions.push(("default".to_string(), default_path));
+    }
+
+    let dir = sessions_dir()?;
+    if dir.exists() {
+        let mut named = Vec::new();
+        for entry in fs::read_dir(&dir)
+            .with_context(|| format!("Failed to read sessions directory {}", dir.display()))?
+        {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json")
+                && let Some(stem) = path.file_stem().and_then(|stem| stem.to_str())
+            {
+                named.push((stem.to_string(), path.clone()));
+            }
+        }
+        named.sort();
+        sessions.extend(named);
+    }
+    Ok(sessions)
+}
@@ -3,16 +3,18 @@ use ic_agent::export::Principal;
 use serde_json::to_string;
 use tracing::info;
 
-use crate::{cli::TaggedEmbeddingsArgs, clients::memory::MemoryClient};
+use crate::{cli::TaggedEmbeddingsArgs, kinic_client::KinicClient};
 
 use super::CommandContext;
 
 pub async fn handle(args: TaggedEmbeddingsArgs, ctx: &CommandContext) -> Result<()> {
-    let client = build_memory_client(&args.memory_id, ctx).await?;
-    let embeddings = client.tagged_embeddings(args.tag.clone()).await?;
+    let client = KinicClient::connect(&ctx.agent_factory).await?;
+    let memory = Principal::from_text(&args.memory_id)
+        .context("Failed to parse canister id for tagged-embeddings command")?;
+    let embeddings = client.tagged_embeddings(&memory, &args.tag).await?;
 
     info!(
-        canister_id = %client.canister_id(),
+        canister_id = %memory,
         tag = %args.tag,
         embedding_count = embeddings.len(),
         "tagged-embeddings fetched"
@@ -21,10 +23,3 @@ pub async fn handle(args: TaggedEmbeddingsArgs, ctx: &CommandContext) -> Result<
     println!("{}", to_string(&embeddings)?);
     Ok(())
 }
-
-async fn build_memory_client(id: &str, ctx: &CommandContext) -> Result<MemoryClient> {
-    let agent = ctx.agent_factory.build().await?;
-    let memory = Principal::from_text(id)
-        .context("Failed to parse canister id for tagged-embeddings command")?;
-    Ok(MemoryClient::new(agent, memory))
-}
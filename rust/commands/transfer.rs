@@ -0,0 +1,103 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Result, anyhow, bail};
+use candid::Nat;
+use icrc_ledger_types::icrc1::{
+    account::Account,
+    transfer::{Memo, TransferArg},
+};
+use tracing::info;
+
+use crate::{
+    cli::TransferArgs,
+    error::KinicError,
+    ledger::{
+        fetch_balance, fetch_fee, format_kinic, nat_to_u128, parse_account, parse_kinic_amount,
+        transfer,
+    },
+};
+
+use super::{CommandContext, prompt::confirm};
+
+const MAX_MEMO_BYTES: usize = 32;
+
+pub async fn handle(args: TransferArgs, ctx: &CommandContext) -> Result<()> {
+    let to = parse_account(&args.to)?;
+    let amount = parse_kinic_amount(&args.amount)?;
+    if amount == 0 {
+        bail!(KinicError::Validation(
+            "Amount must be greater than zero".to_string()
+        ));
+    }
+    let memo = parse_memo(args.memo.as_deref())?;
+
+    let agent = ctx.agent_factory.build().await?;
+    let principal = agent
+        .get_principal()
+        .map_err(|e| anyhow!("Failed to derive principal for current identity: {e}"))?;
+    let fee = fetch_fee(&agent).await?;
+    let fee_e8s = nat_to_u128(&fee)?;
+    let balance = fetch_balance(&agent, ctx.subaccount).await?;
+
+    let total = amount
+        .checked_add(fee_e8s)
+        .ok_or_else(|| anyhow!(KinicError::Validation("Amount is too large".to_string())))?;
+    let from = Account {
+        owner: principal,
+        subaccount: ctx.subaccount,
+    };
+    println!("Transfer preview:");
+    println!("  From:    {from}");
+    println!("  To:      {to}");
+    println!("  Amount:  {} KINIC", format_kinic(amount));
+    println!("  Fee:     {} KINIC", format_kinic(fee_e8s));
+    println!("  Balance: {} KINIC", format_kinic(balance));
+    if balance < total {
+        bail!(KinicError::Ledger(format!(
+            "Insufficient balance: need {} KINIC (amount + fee), have {} KINIC",
+            format_kinic(total),
+            format_kinic(balance)
+        )));
+    }
+    let created_at_time = match args.created_at_time {
+        Some(created_at_time) => created_at_time,
+        None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64,
+    };
+    println!("  Created: {created_at_time} (retry with --created-at-time {created_at_time})");
+    if !confirm("Send this transfer?", args.yes)? {
+        println!("Aborted; nothing was sent.");
+        return Ok(());
+    }
+
+    let block = transfer(
+        &agent,
+        TransferArg {
+            from_subaccount: ctx.subaccount,
+            to,
+            fee: Some(fee),
+            created_at_time: Some(created_at_time),
+            memo,
+            amount: Nat::from(amount),
+        },
+    )
+    .await?;
+
+    info!(%to, amount_e8s = amount, %block, "transfer completed");
+    println!(
+        "Transferred {} KINIC to {to} in block {block}",
+        format_kinic(amount)
+    );
+    Ok(())
+}
+
+fn parse_memo(memo: Option<&str>) -> Result<Option<Memo>> {
+    let Some(memo) = memo else {
+        return Ok(None);
+    };
+    if memo.len() > MAX_MEMO_BYTES {
+        bail!(KinicError::Validation(format!(
+            "Memo must be at most {MAX_MEMO_BYTES} bytes"
+        )));
+    }
+    Ok(Some(Memo::from(memo.as_bytes().to_vec())))
+}
@@ -2,25 +2,21 @@ use anyhow::{Context, Result};
 use ic_agent::export::Principal;
 use tracing::info;
 
-use crate::{cli::UpdateArgs, clients::launcher::LauncherClient};
+use crate::{cli::UpdateArgs, kinic_client::KinicClient};
 
 use super::CommandContext;
 
 pub async fn handle(args: UpdateArgs, ctx: &CommandContext) -> Result<()> {
-    let agent = ctx.agent_factory.build().await?;
-    let client = LauncherClient::new(agent);
+    let client = KinicClient::connect(&ctx.agent_factory).await?;
 
-    let pid = Principal::from_text(&args.memory_id)
-        .context("Failed to parse canister id for update command")?
-        .to_text();
+    let memory = Principal::from_text(&args.memory_id)
+        .context("Failed to parse canister id for update command")?;
+    let pid = memory.to_text();
 
-    client
-        .update_instance(&pid)
-        .await
-        .context("Failed to update instance via launcher canister")?;
+    client.update_instance(&memory).await?;
 
     info!(
-        launcher_id = %client.launcher_id(),
+        launcher_id = %client.launcher().launcher_id(),
         instance = %pid,
         "update instance completed"
     );
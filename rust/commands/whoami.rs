@@ -0,0 +1,79 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Result, anyhow};
+use ic_agent::{Identity, export::Principal};
+use icrc_ledger_types::icrc1::account::Account;
+
+use crate::{
+    agent::KEYRING_IDENTITY_PREFIX,
+    cli::WhoamiArgs,
+    identity_store::{read_stored_identity, stored_delegation_info, stored_principal},
+};
+
+use super::{CommandContext, format_remaining};
+
+pub async fn handle(_args: WhoamiArgs, ctx: &CommandContext) -> Result<()> {
+    // Only local material is read here: no agent is built and no canister is called.
+    if let Some(path) = &ctx.identity_path {
+        // Read the file as-is: no expiry check and no keyring or passphrase unlock, so an
+        // expired or locked session can still be inspected.
+        let stored = read_stored_identity(path)?;
+        let info = stored_delegation_info(&stored)?;
+        print_account(stored_principal(&stored)?, ctx);
+        println!(
+            "Source:       Internet Identity delegation ({})",
+            path.display()
+        );
+        println!(
+            "Key type:     Ed25519 session key delegated by a {} user key",
+            info.user_key_type
+        );
+        println!("Provider:     {}", info.identity_provider);
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64;
+        if info.expiration_ns <= now {
+            println!("Expires in:   expired (run `kinic-cli --ii login` to refresh it)");
+        } else {
+            println!(
+                "Expires in:   {}",
+                format_remaining(info.expiration_ns - now)
+            );
+        }
+        match &info.targets {
+            None => println!("Targets:      unrestricted"),
+            Some(targets) => {
+                let targets: Vec<String> = targets.iter().map(|t| t.to_text()).collect();
+                println!("Targets:      {}", targets.join(", "));
+            }
+        }
+        println!("Key storage:  {}", info.protection);
+        match info.verification_skipped {
+            None => println!("Verification: checked locally when the session key is loaded"),
+            Some(reason) => println!("Verification: skipped locally ({reason})"),
+        }
+    } else {
+        let loaded = ctx.agent_factory.load_identity()?;
+        let name = ctx.agent_factory.identity_suffix();
+        print_account(sender(loaded.identity.as_ref())?, ctx);
+        println!(
+            "Source:       keyring entry {KEYRING_IDENTITY_PREFIX}{name} (dfx identity `{name}`)"
+        );
+        println!("Key type:     {}", loaded.key_type);
+    }
+    Ok(())
+}
+
+fn sender(identity: &dyn Identity) -> Result<Principal> {
+    identity
+        .sender()
+        .map_err(|e| anyhow!("Failed to derive principal for current identity: {e}"))
+}
+
+fn print_account(principal: Principal, ctx: &CommandContext) {
+    let account = Account {
+        owner: principal,
+        subaccount: ctx.subaccount,
+    };
+    println!("Principal:    {principal}");
+    println!("Account:      {account}");
+}
@@ -0,0 +1,36 @@
+//! rust/config_store.rs
+//! Where: Kinic CLI settings file (~/.config/kinic/config.json).
+//! What: Loads optional user settings such as named ledger subaccounts.
+//! Why: Keeps per-project budgets addressable without repeating raw hex on every call.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CliConfig {
+    /// Named subaccounts, each value being a hex subaccount or a numeric index.
+    #[serde(default)]
+    pub subaccounts: BTreeMap<String, String>,
+}
+
+pub fn default_config_path() -> Result<PathBuf> {
+    let home = std::env::var("HOME").context("HOME is not set")?;
+    Ok(PathBuf::from(home).join(".config/kinic/config.json"))
+}
+
+/// Loads the config file, treating a missing file as an empty config.
+pub fn load_config(path: &Path) -> Result<CliConfig> {
+    if !path.exists() {
+        return Ok(CliConfig::default());
+    }
+    let payload = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file at {}", path.display()))?;
+    serde_json::from_str(&payload)
+        .with_context(|| format!("Failed to parse config file at {}", path.display()))
+}
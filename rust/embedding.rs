@@ -4,12 +4,24 @@ use anyhow::{Context, Result, bail};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
+use crate::error::KinicError;
+
 pub(crate) const EMBEDDING_API_ENV_VAR: &str = "EMBEDDING_API_ENDPOINT";
 pub(crate) const DEFAULT_EMBEDDING_API_ENDPOINT: &str = "https://api.kinic.io";
 const LATE_CHUNKING_PATH: &str = "/late-chunking";
 const EMBEDDING_PATH: &str = "/embedding";
 
+/// Splits `text` into chunks and embeds each one via the `/late-chunking` endpoint.
 pub async fn late_chunking(text: &str) -> Result<Vec<LateChunk>> {
+    request_late_chunking(text).await.map_err(embedding_error)
+}
+
+/// Embeds `text` via the `/embedding` endpoint.
+pub async fn fetch_embedding(text: &str) -> Result<Vec<f32>> {
+    request_embedding(text).await.map_err(embedding_error)
+}
+
+async fn request_late_chunking(text: &str) -> Result<Vec<LateChunk>> {
     let url = format!("{}{}", embedding_base_url(), LATE_CHUNKING_PATH);
     let response = Client::new()
         .post(url)
@@ -26,7 +38,7 @@ pub async fn late_chunking(text: &str) -> Result<Vec<LateChunk>> {
     Ok(payload.chunks)
 }
 
-pub async fn fetch_embedding(text: &str) -> Result<Vec<f32>> {
+async fn request_embedding(text: &str) -> Result<Vec<f32>> {
     let url = format!("{}{}", embedding_base_url(), EMBEDDING_PATH);
     let response = Client::new()
         .post(url)
@@ -43,6 +55,14 @@ pub async fn fetch_embedding(text: &str) -> Result<Vec<f32>> {
     Ok(payload.embedding)
 }
 
+/// Dimension of the vectors produced by the configured embedding API, learned from a probe call.
+pub async fn embedding_dimension() -> Result<usize> {
+    let probe = fetch_embedding("dimension probe")
+        .await
+        .context("Failed to probe the embedding API for its dimension")?;
+    Ok(probe.len())
+}
+
 async fn ensure_success(response: reqwest::Response) -> Result<reqwest::Response> {
     if response.status().is_success() {
         return Ok(response);
@@ -53,6 +73,11 @@ async fn ensure_success(response: reqwest::Response) -> Result<reqwest::Response
     bail!("embedding API request failed with status {status}: {body}");
 }
 
+/// Re-raises any failure talking to the embedding service as [`KinicError::Embedding`].
+pub(crate) fn embedding_error(err: anyhow::Error) -> anyhow::Error {
+    KinicError::Embedding(format!("{err:#}")).into()
+}
+
 pub(crate) fn embedding_base_url() -> String {
     env::var(EMBEDDING_API_ENV_VAR).unwrap_or_else(|_| DEFAULT_EMBEDDING_API_ENDPOINT.to_string())
 }
@@ -0,0 +1,212 @@
+//! rust/error.rs
+//! Where: Error taxonomy shared by the CLI, the SDK and the Python bindings.
+//! What: `KinicError` categories, classification of arbitrary `anyhow` chains, and exit codes.
+//! Why: Lets callers tell an expired delegation from a ledger failure or a canister reject.
+
+use candid::types::principal::PrincipalError;
+use ic_agent::AgentError;
+use thiserror::Error;
+
+/// Categorised failure. Raised as `anyhow::Error` like any other error (e.g.
+/// `bail!(KinicError::Validation(..))`); recover the category with [`ErrorKind::of`].
+#[derive(Debug, Error)]
+pub enum KinicError {
+    /// Credentials are missing, expired or cannot be unlocked.
+    #[error("{0}")]
+    Auth(String),
+    /// The replica or another endpoint could not be reached.
+    #[error("{0}")]
+    Network(String),
+    /// A canister rejected the call or trapped.
+    #[error("canister rejected the call (reject code {code}): {message}")]
+    CanisterReject { code: u8, message: String },
+    /// The embedding or chat API failed or returned something unusable.
+    #[error("{0}")]
+    Embedding(String),
+    /// User input was rejected before anything was sent.
+    #[error("{0}")]
+    Validation(String),
+    /// The ledger refused a transfer or approval, or the balance is too low.
+    #[error("{0}")]
+    Ledger(String),
+}
+
+impl KinicError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            KinicError::Auth(_) => ErrorKind::Auth,
+            KinicError::Network(_) => ErrorKind::Network,
+            KinicError::CanisterReject { .. } => ErrorKind::CanisterReject,
+            KinicError::Embedding(_) => ErrorKind::Embedding,
+            KinicError::Validation(_) => ErrorKind::Validation,
+            KinicError::Ledger(_) => ErrorKind::Ledger,
+        }
+    }
+}
+
+/// Category of an error, including [`ErrorKind::Other`] for anything unclassified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    Auth,
+    Network,
+    CanisterReject,
+    Embedding,
+    Validation,
+    Ledger,
+    Other,
+}
+
+impl ErrorKind {
+    /// Classifies `err` by the outermost recognised cause: a [`KinicError`], an agent reject or
+    /// transport failure, or an unparsable principal.
+    pub fn of(err: &anyhow::Error) -> Self {
+        err.chain()
+            .find_map(|cause| {
+                if let Some(err) = cause.downcast_ref::<KinicError>() {
+                    return Some(err.kind());
+                }
+                if let Some(err) = cause.downcast_ref::<AgentError>() {
+                    return agent_error_kind(err);
+                }
+                if cause.downcast_ref::<PrincipalError>().is_some() {
+                    return Some(ErrorKind::Validation);
+                }
+                if cause.downcast_ref::<reqwest::Error>().is_some() {
+                    return Some(ErrorKind::Network);
+                }
+                None
+            })
+            .unwrap_or(ErrorKind::Other)
+    }
+
+    /// Process exit code used by the CLI. 2 is left to clap for usage errors.
+    pub fn exit_code(self) -> u8 {
+        match self {
+            ErrorKind::Other => 1,
+            ErrorKind::Validation => 3,
+            ErrorKind::Auth => 4,
+            ErrorKind::Network => 5,
+            ErrorKind::CanisterReject => 6,
+            ErrorKind::Embedding => 7,
+            ErrorKind::Ledger => 8,
+        }
+    }
+}
+
+fn agent_error_kind(err: &AgentError) -> Option<ErrorKind> {
+    match err {
+        AgentError::CertifiedReject { .. } | AgentError::UncertifiedReject { .. } => {
+            Some(ErrorKind::CanisterReject)
+        }
+        AgentError::TransportError(_)
+        | AgentError::TimeoutWaitingForResponse()
+        | AgentError::HttpError(_)
+        | AgentError::InvalidReplicaUrl(_) => Some(ErrorKind::Network),
+        AgentError::SigningError(_) => Some(ErrorKind::Auth),
+        AgentError::PrincipalError(_) => Some(ErrorKind::Validation),
+        _ => None,
+    }
+}
+
+/// Reject details carried by an agent error anywhere in `err`'s chain, as a [`KinicError`].
+pub fn canister_reject(err: &anyhow::Error) -> Option<KinicError> {
+    err.chain()
+        .find_map(|cause| match cause.downcast_ref::<AgentError>()? {
+            AgentError::CertifiedReject { reject, .. }
+            | AgentError::UncertifiedReject { reject, .. } => Some(KinicError::CanisterReject {
+                code: reject.reject_code as u8,
+                message: reject.reject_message.clone(),
+            }),
+            _ => None,
+        })
+}
+
+/// Whether `err` is the replica reporting that the canister has no such method (`IC0536`),
+/// i.e. the canister predates the call rather than failing it.
+pub fn is_method_not_found(err: &anyhow::Error) -> bool {
+    err.chain()
+        .any(|cause| match cause.downcast_ref::<AgentError>() {
+            Some(
+                AgentError::CertifiedReject { reject, .. }
+                | AgentError::UncertifiedReject { reject, .. },
+            ) => reject.error_code.as_deref() == Some("IC0536"),
+            _ => false,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{Context, anyhow};
+    use ic_agent::{
+        agent::{RejectCode, RejectResponse},
+        export::Principal,
+    };
+
+    use super::*;
+
+    fn reject(error_code: &str) -> AgentError {
+        AgentError::UncertifiedReject {
+            reject: RejectResponse {
+                reject_code: RejectCode::DestinationInvalid,
+                reject_message: "no such method".to_string(),
+                error_code: Some(error_code.to_string()),
+            },
+            operation: None,
+        }
+    }
+
+    #[test]
+    fn each_category_has_its_exit_code() {
+        let cases = [
+            (KinicError::Validation("v".into()), ErrorKind::Validation, 3),
+            (KinicError::Auth("a".into()), ErrorKind::Auth, 4),
+            (KinicError::Network("n".into()), ErrorKind::Network, 5),
+            (
+                KinicError::CanisterReject {
+                    code: 5,
+                    message: "trap".into(),
+                },
+                ErrorKind::CanisterReject,
+                6,
+            ),
+            (KinicError::Embedding("e".into()), ErrorKind::Embedding, 7),
+            (KinicError::Ledger("l".into()), ErrorKind::Ledger, 8),
+        ];
+        for (err, kind, code) in cases {
+            let err = anyhow::Error::from(err);
+            assert_eq!(ErrorKind::of(&err), kind);
+            assert_eq!(kind.exit_code(), code);
+        }
+        assert_eq!(ErrorKind::of(&anyhow!("plain")), ErrorKind::Other);
+        assert_eq!(ErrorKind::Other.exit_code(), 1);
+    }
+
+    #[test]
+    fn classification_sees_through_context() {
+        let err = Err::<(), _>(KinicError::Auth("delegation expired".into()))
+            .context("Failed to load identity")
+            .context("Failed to build agent")
+            .unwrap_err();
+        assert_eq!(ErrorKind::of(&err), ErrorKind::Auth);
+        assert_eq!(ErrorKind::of(&err).exit_code(), 4);
+    }
+
+    #[test]
+    fn foreign_errors_are_classified() {
+        let principal = Principal::from_text("not-a-principal")
+            .context("Failed to parse memory id")
+            .unwrap_err();
+        assert_eq!(ErrorKind::of(&principal), ErrorKind::Validation);
+
+        let rejected = anyhow::Error::from(reject("IC0503")).context("Failed to call insert");
+        assert_eq!(ErrorKind::of(&rejected), ErrorKind::CanisterReject);
+        assert!(!is_method_not_found(&rejected));
+    }
+
+    #[test]
+    fn method_not_found_is_detected_in_the_chain() {
+        let err = anyhow::Error::from(reject("IC0536")).context("Failed to call get_metadata");
+        assert!(is_method_not_found(&err));
+        assert!(!is_method_not_found(&anyhow!("IC0536")));
+    }
+}
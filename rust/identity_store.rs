@@ -4,28 +4,102 @@ use std::{
     time::{SystemTime, UNIX_EPOCH},
 };
 
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result, anyhow, bail};
 use der::{Decode, SliceReader};
 use ic_agent::Identity;
 use ic_agent::export::Principal;
 use ic_agent::identity::{BasicIdentity, DelegatedIdentity, DelegationError, SignedDelegation};
 use ic_ed25519::PublicKey;
 use pkcs8::{ObjectIdentifier, spki::SubjectPublicKeyInfoRef};
-use ring::signature::Ed25519KeyPair;
+use ring::{
+    aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey},
+    rand::SecureRandom,
+    signature::Ed25519KeyPair,
+};
 use serde::{Deserialize, Serialize};
 use std::fs::OpenOptions;
-use std::io::Write;
+use std::io::{IsTerminal, Write};
 use tracing::warn;
 
+use crate::error::KinicError;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StoredIdentity {
     pub version: u8,
     pub identity_provider: String,
     pub user_public_key_hex: String,
+    /// Plaintext session key; empty when `protection` keeps it elsewhere.
+    #[serde(default)]
     pub session_pkcs8_hex: String,
     pub delegations: Vec<SignedDelegation>,
     pub expiration_ns: u64,
     pub created_at_ns: u64,
+    /// Files written before protection existed have no field and are read as plaintext.
+    #[serde(default)]
+    pub protection: KeyProtection,
+    /// Callback port used at login. It is part of the II derivation origin, so `--relogin`
+    /// must reuse it to get the same principal back.
+    #[serde(default)]
+    pub callback_port: Option<u16>,
+    /// Delegation lifetime requested at login, reused by `--relogin`.
+    #[serde(default)]
+    pub ttl_hours: Option<u64>,
+}
+
+/// How the session key is kept at rest.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum KeyProtection {
+    #[default]
+    None,
+    /// The hex session key lives in the OS keyring under this account name.
+    Keyring { account: String },
+    /// AES-256-GCM with a key derived from a passphrase by Argon2id.
+    Passphrase {
+        m_cost: u32,
+        t_cost: u32,
+        p_cost: u32,
+        salt_hex: String,
+        nonce_hex: String,
+        ciphertext_hex: String,
+    },
+}
+
+impl KeyProtection {
+    pub fn label(&self) -> &'static str {
+        match self {
+            KeyProtection::None => "none",
+            KeyProtection::Keyring { .. } => "keyring",
+            KeyProtection::Passphrase { .. } => "passphrase",
+        }
+    }
+
+    pub fn mode(&self) -> ProtectionMode {
+        match self {
+            KeyProtection::None => ProtectionMode::None,
+            KeyProtection::Keyring { .. } => ProtectionMode::Keyring,
+            KeyProtection::Passphrase { .. } => ProtectionMode::Passphrase,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtectionMode {
+    None,
+    Keyring,
+    Passphrase,
+}
+
+/// Facts about a saved delegation, surfaced by `whoami`.
+pub struct DelegationInfo {
+    pub identity_provider: String,
+    pub expiration_ns: u64,
+    pub user_key_type: &'static str,
+    /// Canisters the delegation is restricted to; `None` means unrestricted.
+    pub targets: Option<Vec<Principal>>,
+    /// Why local verification of the chain was skipped, if it was.
+    pub verification_skipped: Option<&'static str>,
+    pub protection: &'static str,
 }
 
 pub struct SessionKeyMaterial {
@@ -33,9 +107,36 @@ pub struct SessionKeyMaterial {
     pub public_key: Vec<u8>,
 }
 
+const SESSION_KEYRING_SERVICE: &str = "kinic-cli";
+const KEY_LEN: usize = 32;
+const PASSPHRASE_ENV: &str = "KINIC_SESSION_PASSPHRASE";
+
+/// Delegations closer than this to expiry trigger a warning (or a re-login with `--relogin`).
+pub const EXPIRY_WARNING_NS: u64 = 30 * 60 * 1_000_000_000;
+
 pub fn default_identity_path() -> Result<PathBuf> {
+    Ok(config_dir()?.join("identity.json"))
+}
+
+pub fn sessions_dir() -> Result<PathBuf> {
+    Ok(config_dir()?.join("sessions"))
+}
+
+/// Path of a named session. Names are restricted so they cannot escape the sessions directory.
+pub fn session_path(name: &str) -> Result<PathBuf> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        bail!("Session names may only contain letters, digits, '-' and '_'");
+    }
+    Ok(sessions_dir()?.join(format!("{name}.json")))
+}
+
+fn config_dir() -> Result<PathBuf> {
     let home = std::env::var("HOME").context("HOME is not set")?;
-    Ok(PathBuf::from(home).join(".config/kinic/identity.json"))
+    Ok(PathBuf::from(home).join(".config/kinic"))
 }
 
 pub fn generate_session_key() -> Result<SessionKeyMaterial> {
@@ -54,30 +155,43 @@ pub fn generate_session_key() -> Result<SessionKeyMaterial> {
 }
 
 pub fn load_delegated_identity(path: &Path) -> Result<DelegatedIdentity> {
-    let payload = fs::read_to_string(path)
-        .with_context(|| format!("Failed to read identity file at {}", path.display()))?;
-    let stored: StoredIdentity =
-        serde_json::from_str(&payload).context("Failed to parse identity.json")?;
-    ensure_not_expired(&stored)?;
+    load_delegated_identity_with_info(path).map(|(identity, _)| identity)
+}
 
-    let user_public_key_raw =
-        hex::decode(&stored.user_public_key_hex).context("Failed to decode user public key")?;
-    let user_public_key =
-        normalize_spki_key(&user_public_key_raw).context("Unsupported user public key format")?;
-    let pkcs8 = hex::decode(&stored.session_pkcs8_hex).context("Failed to decode session key")?;
+pub fn load_delegated_identity_with_info(
+    path: &Path,
+) -> Result<(DelegatedIdentity, DelegationInfo)> {
+    let stored = read_stored_identity(path).map_err(|err| {
+        KinicError::Auth(format!(
+            "{err:#}. Run `kinic-cli --ii login` to create a delegation."
+        ))
+    })?;
+    let remaining_ns = ensure_not_expired(&stored)?;
+    if remaining_ns < EXPIRY_WARNING_NS {
+        let minutes = remaining_ns / (60 * 1_000_000_000);
+        warn!(minutes, "Internet Identity delegation is about to expire");
+        eprintln!(
+            "Warning: Internet Identity delegation expires in {minutes} min. Run `kinic-cli --ii login` or pass --relogin to refresh it."
+        );
+    }
+
+    let user_public_key = stored_user_public_key(&stored)?;
+    let pkcs8 = session_key_pkcs8(&stored)?;
     let key_pair =
         Ed25519KeyPair::from_pkcs8(&pkcs8).map_err(|_| anyhow!("Invalid session key"))?;
     let session_identity = BasicIdentity::from_key_pair(key_pair);
     let delegations = normalize_delegations(&stored.delegations)?;
+    let mut info = stored_delegation_info(&stored)?;
 
-    if is_canister_signature_key(&user_public_key)? {
+    if info.verification_skipped.is_some() {
         warn!("Delegation chain uses canister signature keys; skipping local verification.");
         eprintln!("Warning: delegation uses canister signature keys; skipped local verification.");
-        return Ok(DelegatedIdentity::new_unchecked(
+        let identity = DelegatedIdentity::new_unchecked(
             user_public_key,
             Box::new(session_identity),
             delegations,
-        ));
+        );
+        return Ok((identity, info));
     }
 
     let delegated = DelegatedIdentity::new(
@@ -86,23 +200,242 @@ pub fn load_delegated_identity(path: &Path) -> Result<DelegatedIdentity> {
         delegations.clone(),
     );
     match delegated {
-        Ok(identity) => Ok(identity),
+        Ok(identity) => Ok((identity, info)),
         Err(DelegationError::UnknownAlgorithm) => {
             warn!("Delegation chain uses an unknown algorithm; skipping local verification.");
             eprintln!("Warning: delegation uses an unknown algorithm; skipped local verification.");
             let key_pair =
                 Ed25519KeyPair::from_pkcs8(&pkcs8).map_err(|_| anyhow!("Invalid session key"))?;
             let session_identity = BasicIdentity::from_key_pair(key_pair);
-            Ok(DelegatedIdentity::new_unchecked(
+            info.verification_skipped = Some("unknown delegation algorithm");
+            let identity = DelegatedIdentity::new_unchecked(
                 user_public_key,
                 Box::new(session_identity),
                 delegations,
-            ))
+            );
+            Ok((identity, info))
         }
         Err(err) => Err(err.into()),
     }
 }
 
+/// Describes a stored delegation without unlocking the session key or checking its expiry.
+/// `verification_skipped` is only set when the user key rules out local verification.
+pub fn stored_delegation_info(stored: &StoredIdentity) -> Result<DelegationInfo> {
+    let user_public_key = stored_user_public_key(stored)?;
+    let delegations = normalize_delegations(&stored.delegations)?;
+    Ok(DelegationInfo {
+        identity_provider: stored.identity_provider.clone(),
+        expiration_ns: stored.expiration_ns,
+        user_key_type: describe_key_algorithm(&user_public_key),
+        targets: delegation_targets(&delegations),
+        verification_skipped: is_canister_signature_key(&user_public_key)?
+            .then_some("canister signature user key"),
+        protection: stored.protection.label(),
+    })
+}
+
+/// The principal a stored delegation signs for, derived from its user public key.
+pub fn stored_principal(stored: &StoredIdentity) -> Result<Principal> {
+    let user_public_key = stored_user_public_key(stored)?;
+    Ok(Principal::self_authenticating(&user_public_key))
+}
+
+fn stored_user_public_key(stored: &StoredIdentity) -> Result<Vec<u8>> {
+    let user_public_key_raw =
+        hex::decode(&stored.user_public_key_hex).context("Failed to decode user public key")?;
+    normalize_spki_key(&user_public_key_raw).context("Unsupported user public key format")
+}
+
+pub fn read_stored_identity(path: &Path) -> Result<StoredIdentity> {
+    let payload = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read identity file at {}", path.display()))?;
+    serde_json::from_str(&payload).context("Failed to parse identity.json")
+}
+
+/// Nanoseconds until the stored delegation expires, or zero if it already has.
+pub fn remaining_validity_ns(stored: &StoredIdentity) -> Result<u64> {
+    Ok(stored.expiration_ns.saturating_sub(current_time_ns()?))
+}
+
+/// Stores `pkcs8` in `stored` according to `mode`. Any previous keyring entry is left alone so
+/// callers can delete it only after the new file is safely written.
+pub fn protect_session_key(
+    stored: &mut StoredIdentity,
+    pkcs8: &[u8],
+    mode: ProtectionMode,
+) -> Result<()> {
+    let rng = ring::rand::SystemRandom::new();
+    stored.session_pkcs8_hex.clear();
+    stored.protection = match mode {
+        ProtectionMode::None => {
+            stored.session_pkcs8_hex = hex::encode(pkcs8);
+            KeyProtection::None
+        }
+        ProtectionMode::Keyring => {
+            let mut id = [0u8; 8];
+            rng.fill(&mut id)
+                .map_err(|_| anyhow!("Failed to generate keyring entry name"))?;
+            let account = format!("ii_session_{}", hex::encode(id));
+            keyring::Entry::new(SESSION_KEYRING_SERVICE, &account)?
+                .set_password(&hex::encode(pkcs8))
+                .map_err(|e| anyhow!("Failed to store session key in the keyring: {e}"))?;
+            KeyProtection::Keyring { account }
+        }
+        ProtectionMode::Passphrase => {
+            let passphrase = read_passphrase(true)?;
+            let params = argon2::Params::default();
+            let mut salt = [0u8; 16];
+            let mut nonce = [0u8; NONCE_LEN];
+            rng.fill(&mut salt)
+                .and_then(|_| rng.fill(&mut nonce))
+                .map_err(|_| anyhow!("Failed to generate encryption parameters"))?;
+            let key = derive_passphrase_key(&passphrase, &salt, &params)?;
+            let mut ciphertext = pkcs8.to_vec();
+            key.seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(stored.user_public_key_hex.as_bytes()),
+                &mut ciphertext,
+            )
+            .map_err(|_| anyhow!("Failed to encrypt session key"))?;
+            KeyProtection::Passphrase {
+                m_cost: params.m_cost(),
+                t_cost: params.t_cost(),
+                p_cost: params.p_cost(),
+                salt_hex: hex::encode(salt),
+                nonce_hex: hex::encode(nonce),
+                ciphertext_hex: hex::encode(ciphertext),
+            }
+        }
+    };
+    Ok(())
+}
+
+/// Returns the session key in PKCS#8 form, unlocking it from the keyring or passphrase if needed.
+pub fn session_key_pkcs8(stored: &StoredIdentity) -> Result<Vec<u8>> {
+    match &stored.protection {
+        KeyProtection::None => {
+            hex::decode(&stored.session_pkcs8_hex).context("Failed to decode session key")
+        }
+        KeyProtection::Keyring { account } => {
+            let encoded = keyring::Entry::new(SESSION_KEYRING_SERVICE, account)?
+                .get_password()
+                .map_err(|e| {
+                    KinicError::Auth(format!("Failed to read session key from the keyring: {e}"))
+                })?;
+            hex::decode(encoded).context("Failed to decode session key from the keyring")
+        }
+        KeyProtection::Passphrase {
+            m_cost,
+            t_cost,
+            p_cost,
+            salt_hex,
+            nonce_hex,
+            ciphertext_hex,
+        } => {
+            let params = argon2::Params::new(*m_cost, *t_cost, *p_cost, Some(KEY_LEN))
+                .map_err(|e| anyhow!("Invalid Argon2 parameters in identity file: {e}"))?;
+            let salt = hex::decode(salt_hex).context("Failed to decode salt")?;
+            let nonce = Nonce::try_assume_unique_for_key(
+                &hex::decode(nonce_hex).context("Failed to decode nonce")?,
+            )
+            .map_err(|_| anyhow!("Invalid nonce in identity file"))?;
+            let mut buffer = hex::decode(ciphertext_hex).context("Failed to decode ciphertext")?;
+            let key = derive_passphrase_key(&read_passphrase(false)?, &salt, &params)?;
+            let plaintext = key
+                .open_in_place(
+                    nonce,
+                    Aad::from(stored.user_public_key_hex.as_bytes()),
+                    &mut buffer,
+                )
+                .map_err(|_| {
+                    KinicError::Auth("Failed to decrypt session key: wrong passphrase?".to_string())
+                })?;
+            Ok(plaintext.to_vec())
+        }
+    }
+}
+
+/// Deletes the keyring entry referenced by `protection`, if any.
+pub fn forget_protected_key(protection: &KeyProtection) -> Result<()> {
+    if let KeyProtection::Keyring { account } = protection {
+        match keyring::Entry::new(SESSION_KEYRING_SERVICE, account)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(err) => return Err(anyhow!("Failed to delete keyring entry {account}: {err}")),
+        }
+    }
+    Ok(())
+}
+
+fn derive_passphrase_key(
+    passphrase: &str,
+    salt: &[u8],
+    params: &argon2::Params,
+) -> Result<LessSafeKey> {
+    let mut key = [0u8; KEY_LEN];
+    argon2::Argon2::new(
+        argon2::Algorithm::Argon2id,
+        argon2::Version::V0x13,
+        params.clone(),
+    )
+    .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+    .map_err(|e| anyhow!("Failed to derive key from passphrase: {e}"))?;
+    let key = UnboundKey::new(&AES_256_GCM, &key)
+        .map_err(|_| anyhow!("Failed to build encryption key"))?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// Reads the passphrase from `KINIC_SESSION_PASSPHRASE` or, interactively, from the terminal.
+fn read_passphrase(confirm: bool) -> Result<String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    if !std::io::stdin().is_terminal() {
+        bail!(KinicError::Auth(format!(
+            "Session key is passphrase protected; set {PASSPHRASE_ENV} or run interactively"
+        )));
+    }
+    let passphrase = rpassword::prompt_password("Session key passphrase: ")
+        .context("Failed to read passphrase")?;
+    if confirm {
+        let again = rpassword::prompt_password("Repeat passphrase: ")
+            .context("Failed to read passphrase")?;
+        if passphrase != again {
+            bail!("Passphrases do not match");
+        }
+        if passphrase.is_empty() {
+            bail!("Passphrase must not be empty");
+        }
+    }
+    Ok(passphrase)
+}
+
+/// Overwrites the identity file before unlinking it so the session key does not linger on disk.
+/// A keyring-held session key is deleted as well. Returns `false` when there was nothing to remove.
+pub fn remove_identity(path: &Path) -> Result<bool> {
+    if let Ok(stored) = read_stored_identity(path) {
+        forget_protected_key(&stored.protection)?;
+    }
+    let len = match fs::metadata(path) {
+        Ok(metadata) => metadata.len(),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => {
+            return Err(err).with_context(|| format!("Failed to inspect {}", path.display()));
+        }
+    };
+    {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .open(path)
+            .with_context(|| format!("Failed to open {} for wiping", path.display()))?;
+        file.write_all(&vec![0u8; len as usize])
+            .context("Failed to overwrite identity file")?;
+        file.sync_all().context("Failed to sync identity file")?;
+    }
+    fs::remove_file(path).with_context(|| format!("Failed to remove {}", path.display()))?;
+    Ok(true)
+}
+
 pub fn derive_principal_from_user_key(user_public_key_raw: &[u8]) -> Result<Principal> {
     // Internet Identity may return either SPKI DER or raw Ed25519. Normalize to SPKI before deriving.
     let user_public_key =
@@ -155,17 +488,22 @@ pub fn save_identity(path: &Path, stored: &StoredIdentity) -> Result<()> {
     Ok(())
 }
 
-fn ensure_not_expired(stored: &StoredIdentity) -> Result<()> {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .context("System time before UNIX_EPOCH")?;
-    let now_ns = u64::try_from(now.as_nanos()).context("System time overflow")?;
+fn ensure_not_expired(stored: &StoredIdentity) -> Result<u64> {
+    let now_ns = current_time_ns()?;
     if now_ns >= stored.expiration_ns {
-        return Err(anyhow!(
-            "Saved Internet Identity delegation has expired. Run `kinic-cli login` again."
+        bail!(KinicError::Auth(
+            "Saved Internet Identity delegation has expired. Run `kinic-cli --ii login` again or pass --relogin."
+                .to_string()
         ));
     }
-    Ok(())
+    Ok(stored.expiration_ns - now_ns)
+}
+
+fn current_time_ns() -> Result<u64> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .context("System time before UNIX_EPOCH")?;
+    u64::try_from(now.as_nanos()).context("System time overflow")
 }
 
 fn normalize_delegations(entries: &[SignedDelegation]) -> Result<Vec<SignedDelegation>> {
@@ -200,6 +538,33 @@ pub fn normalize_spki_key(bytes: &[u8]) -> Result<Vec<u8>> {
     Err(anyhow!("Unknown public key encoding"))
 }
 
+/// Intersects the target restrictions of every delegation in the chain.
+pub fn delegation_targets(delegations: &[SignedDelegation]) -> Option<Vec<Principal>> {
+    delegations
+        .iter()
+        .filter_map(|entry| entry.delegation.targets.clone())
+        .reduce(|acc, targets| {
+            acc.into_iter()
+                .filter(|target| targets.contains(target))
+                .collect()
+        })
+}
+
+fn describe_key_algorithm(spki_der: &[u8]) -> &'static str {
+    let Ok(mut reader) = SliceReader::new(spki_der) else {
+        return "unknown";
+    };
+    let Ok(spki) = SubjectPublicKeyInfoRef::decode(&mut reader) else {
+        return "unknown";
+    };
+    match spki.algorithm.oid.to_string().as_str() {
+        "1.3.101.112" => "Ed25519",
+        "1.2.840.10045.2.1" => "ECDSA",
+        "1.3.6.1.4.1.56387.1.2" => "canister signature",
+        _ => "unknown",
+    }
+}
+
 fn is_canister_signature_key(bytes: &[u8]) -> Result<bool> {
     let spki = SubjectPublicKeyInfoRef::decode(
         &mut SliceReader::new(bytes).map_err(|_| anyhow!("parse"))?,
@@ -0,0 +1,377 @@
+//! rust/kinic_client.rs
+//! Where: Public SDK entry point of the crate.
+//! What: `KinicClient`, one long-lived `Agent` with typed async methods for memory workflows.
+//! Why: Lets Rust services embed Kinic without going through argv; the CLI builds on it too.
+
+use std::{cmp::Ordering, future::Future, sync::Arc};
+
+use anyhow::{Context, Result, anyhow};
+use candid::Nat;
+use ic_agent::{Agent, export::Principal};
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::task::JoinSet;
+
+use crate::{
+    agent::AgentFactory,
+    clients::{
+        launcher::{LauncherClient, State},
+        memory::{KnownDims, MemoryClient, MemoryMetadata, Role},
+    },
+    commands::ask_ai::{AskAiResult, ask_ai_with_client},
+    embedding::{fetch_embedding, late_chunking},
+    ledger::{fetch_balance, fetch_fee},
+};
+
+/// Kinic API client bound to one identity and network.
+///
+/// Cloning is cheap and shares the underlying agent and the embedding dimensions learned per
+/// memory, so one client can serve a whole process.
+#[derive(Clone)]
+pub struct KinicClient {
+    agent: Agent,
+    subaccount: Option<Subaccount>,
+    dims: Arc<KnownDims>,
+}
+
+impl KinicClient {
+    /// Wraps an agent that is already configured (identity, URL and root key).
+    pub fn new(agent: Agent) -> Self {
+        Self {
+            agent,
+            subaccount: None,
+            dims: Arc::default(),
+        }
+    }
+
+    /// Builds the agent from `factory` (keyring identity or injected delegation).
+    pub async fn connect(factory: &AgentFactory) -> Result<Self> {
+        Ok(Self::new(factory.build().await?))
+    }
+
+    /// Uses `subaccount` for balance queries and launcher approvals.
+    pub fn with_subaccount(mut self, subaccount: Option<Subaccount>) -> Self {
+        self.subaccount = subaccount;
+        self
+    }
+
+    pub fn agent(&self) -> &Agent {
+        &self.agent
+    }
+
+    pub fn subaccount(&self) -> Option<Subaccount> {
+        self.subaccount
+    }
+
+    /// Principal of the identity the agent signs with.
+    pub fn principal(&self) -> Result<Principal> {
+        self.agent
+            .get_principal()
+            .map_err(|e| anyhow!("Failed to derive principal for current identity: {e}"))
+    }
+
+    /// Ledger account used for balances and approvals.
+    pub fn account(&self) -> Result<Account> {
+        Ok(Account {
+            owner: self.principal()?,
+            subaccount: self.subaccount,
+        })
+    }
+
+    /// Low-level client for the launcher canister.
+    pub fn launcher(&self) -> LauncherClient {
+        LauncherClient::new(self.agent.clone())
+    }
+
+    /// Low-level client for one memory canister.
+    pub fn memory(&self, memory: Principal) -> MemoryClient {
+        MemoryClient::with_dims(self.agent.clone(), memory, self.dims.clone())
+    }
+
+    /// Embedding dimension this client has learned for `memory`, if any.
+    pub fn known_dim(&self, memory: &Principal) -> Option<usize> {
+        self.memory(*memory).known_dim()
+    }
+
+    /// Records the embedding dimension of `memory`, e.g. from a cache kept across runs, so
+    /// inserts are checked against it without a metadata query.
+    pub fn remember_dim(&self, memory: Principal, dim: usize) {
+        self.dims.record(memory, Some(dim));
+    }
+
+    /// Approves the deployment price and deploys a new memory, returning its canister id.
+    ///
+    /// Does not wait for the memory to reach `Running`; poll [`Self::list_memories`] for that.
+    pub async fn create_memory(&self, name: &str, description: &str) -> Result<Principal> {
+        let price = self.deployment_price().await?;
+        let fee = self.ledger_fee().await?;
+        self.approve_deployment(&price, &fee).await?;
+        self.deploy_memory(name, description).await
+    }
+
+    /// Price in e8s the launcher charges to deploy a memory.
+    pub async fn deployment_price(&self) -> Result<Nat> {
+        self.launcher().fetch_deployment_price().await
+    }
+
+    /// Fee in e8s the ledger currently charges per transfer or approval.
+    pub async fn ledger_fee(&self) -> Result<Nat> {
+        fetch_fee(&self.agent).await
+    }
+
+    /// Lets the launcher pull `price` from [`Self::account`] for the next deployment, paying
+    /// `fee` (from [`Self::ledger_fee`]) for the approval.
+    pub async fn approve_deployment(&self, price: &Nat, fee: &Nat) -> Result<()> {
+        self.launcher()
+            .approve_launcher(price, fee, self.subaccount)
+            .await
+    }
+
+    /// Deploys a memory after [`Self::approve_deployment`], returning its canister id.
+    pub async fn deploy_memory(&self, name: &str, description: &str) -> Result<Principal> {
+        let id = self.launcher().deploy_memory(name, description).await?;
+        Principal::from_text(&id)
+            .with_context(|| format!("Launcher returned an invalid canister id: {id}"))
+    }
+
+    /// Memories deployed by the current identity, in every deployment state.
+    pub async fn list_memories(&self) -> Result<Vec<State>> {
+        self.launcher().list_memories().await
+    }
+
+    /// Deployment state of `memory`, or `None` while the launcher does not report it yet.
+    pub async fn memory_state(&self, memory: &Principal) -> Result<Option<State>> {
+        self.launcher().instance_state(memory).await
+    }
+
+    /// Name, dimension, record count and cycles reported by `memory`.
+    pub async fn metadata(&self, memory: &Principal) -> Result<MemoryMetadata> {
+        self.memory(*memory).metadata().await
+    }
+
+    /// Chunks and embeds `text` via the embedding API and stores each chunk under `tag`.
+    /// Returns the number of chunks inserted.
+    pub async fn insert_text(&self, memory: &Principal, tag: &str, text: &str) -> Result<usize> {
+        let client = self.memory(*memory);
+        let chunks = late_chunking(text).await?;
+        let count = chunks.len();
+        for chunk in chunks {
+            client
+                .insert(chunk.embedding, &tagged_text(tag, &chunk.sentence))
+                .await?;
+        }
+        Ok(count)
+    }
+
+    /// Stores `text` under `tag` with a precomputed embedding.
+    pub async fn insert_embedding(
+        &self,
+        memory: &Principal,
+        tag: &str,
+        text: &str,
+        embedding: Vec<f32>,
+    ) -> Result<()> {
+        self.memory(*memory)
+            .insert(embedding, &tagged_text(tag, text))
+            .await
+    }
+
+    /// Stores `(tag, text, embedding)` entries, keeping several inserts in flight.
+    /// Returns the number of entries inserted. On error, the message names the failing entry's
+    /// index, and other entries may already be stored.
+    pub async fn insert_embeddings(
+        &self,
+        memory: &Principal,
+        entries: Vec<(String, String, Vec<f32>)>,
+    ) -> Result<usize> {
+        let count = entries.len();
+        run_bounded(entries, |index, (tag, text, embedding)| {
+            let client = self.memory(*memory);
+            async move {
+                client
+                    .insert(embedding, &tagged_text(&tag, &text))
+                    .await
+                    .with_context(|| {
+                        format!("Failed to insert row {index}; other rows may already be stored")
+                    })
+            }
+        })
+        .await?;
+        Ok(count)
+    }
+
+    /// Embeds `query` and returns `(score, stored text)` pairs, best match first.
+    pub async fn search(&self, memory: &Principal, query: &str) -> Result<Vec<(f32, String)>> {
+        let embedding = fetch_embedding(query).await?;
+        self.search_embedding(memory, embedding).await
+    }
+
+    /// Searches with a precomputed embedding; results are sorted best match first.
+    pub async fn search_embedding(
+        &self,
+        memory: &Principal,
+        embedding: Vec<f32>,
+    ) -> Result<Vec<(f32, String)>> {
+        let mut results = self.memory(*memory).search(embedding).await?;
+        sort_by_score(&mut results);
+        Ok(results)
+    }
+
+    /// Searches with each embedding in `embeddings`; one sorted result list per input, in order.
+    pub async fn search_embeddings(
+        &self,
+        memory: &Principal,
+        embeddings: Vec<Vec<f32>>,
+    ) -> Result<Vec<Vec<(f32, String)>>> {
+        run_bounded(embeddings, |index, embedding| {
+            let client = self.memory(*memory);
+            async move {
+                let mut results = client
+                    .search(embedding)
+                    .await
+                    .with_context(|| format!("Failed to search with row {index}"))?;
+                sort_by_score(&mut results);
+                Ok(results)
+            }
+        })
+        .await
+    }
+
+    /// Embeddings stored under `tag`.
+    pub async fn tagged_embeddings(&self, memory: &Principal, tag: &str) -> Result<Vec<Vec<f32>>> {
+        self.memory(*memory)
+            .tagged_embeddings(tag.to_string())
+            .await
+    }
+
+    /// Searches `memory` and asks the LLM to answer `query` from the top `top_k` results.
+    pub async fn ask(
+        &self,
+        memory: &Principal,
+        query: &str,
+        top_k: usize,
+        language: &str,
+    ) -> Result<AskAiResult> {
+        ask_ai_with_client(&self.memory(*memory), query, top_k, language).await
+    }
+
+    /// KINIC balance of [`Self::account`] in e8s.
+    pub async fn balance(&self) -> Result<u128> {
+        fetch_balance(&self.agent, self.subaccount).await
+    }
+
+    /// Every record of `memory` as `(embedding, text)`; the memory must fit in one query reply.
+    pub async fn export(&self, memory: &Principal) -> Result<Vec<(Vec<f32>, String)>> {
+        self.memory(*memory).export().await
+    }
+
+    /// Clears `memory` and sets its embedding dimension. All stored entries are lost.
+    pub async fn reset(&self, memory: &Principal, dim: usize) -> Result<()> {
+        self.memory(*memory).reset(dim).await
+    }
+
+    /// Grants `role` on `memory` to `user`.
+    pub async fn add_user(&self, memory: &Principal, user: Principal, role: Role) -> Result<()> {
+        self.memory(*memory)
+            .add_new_user(user, role.code())
+            .await
+            .context("Failed to add new user to memory canister")
+    }
+
+    /// Upgrades `memory` to the launcher's current memory canister wasm.
+    pub async fn update_instance(&self, memory: &Principal) -> Result<()> {
+        self.launcher()
+            .update_instance(&memory.to_text())
+            .await
+            .context("Failed to update instance via launcher canister")
+    }
+}
+
+/// Payload format the CLI stores alongside each embedding.
+pub(crate) fn tagged_text(tag: &str, sentence: &str) -> String {
+    json!({ "tag": tag, "sentence": sentence }).to_string()
+}
+
+/// Splits a payload written by [`tagged_text`]; other text is returned as-is with no tag.
+pub(crate) fn split_tagged_text(stored: String) -> (Option<String>, String) {
+    #[derive(Deserialize)]
+    struct Stored {
+        tag: Option<String>,
+        sentence: String,
+    }
+    match serde_json::from_str::<Stored>(&stored) {
+        Ok(parsed) => (parsed.tag, parsed.sentence),
+        Err(_) => (None, stored),
+    }
+}
+
+/// Calls allowed in flight at once by the batch methods.
+const BATCH_CONCURRENCY: usize = 16;
+
+/// Runs `call(index, input)` on every input with at most [`BATCH_CONCURRENCY`] tasks in flight
+/// and returns the outputs in input order. The first error aborts the remaining tasks.
+async fn run_bounded<I, O, F, Fut>(inputs: Vec<I>, call: F) -> Result<Vec<O>>
+where
+    O: Send + 'static,
+    F: Fn(usize, I) -> Fut,
+    Fut: Future<Output = Result<O>> + Send + 'static,
+{
+    let mut outputs: Vec<Option<O>> = std::iter::repeat_with(|| None).take(inputs.len()).collect();
+    let mut tasks = JoinSet::new();
+    for (index, input) in inputs.into_iter().enumerate() {
+        if tasks.len() >= BATCH_CONCURRENCY
+            && let Some(joined) = tasks.join_next().await
+        {
+            let (done, output) = joined??;
+            outputs[done] = Some(output);
+        }
+        let task = call(index, input);
+        tasks.spawn(async move { task.await.map(|output| (index, output)) });
+    }
+    while let Some(joined) = tasks.join_next().await {
+        let (done, output) = joined??;
+        outputs[done] = Some(output);
+    }
+    Ok(outputs.into_iter().flatten().collect())
+}
+
+pub(crate) fn sort_by_score(results: &mut [(f32, String)]) {
+    results.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::bail;
+
+    #[tokio::test]
+    async fn run_bounded_keeps_input_order() {
+        let inputs: Vec<u64> = (0..40).collect();
+        let outputs = run_bounded(inputs, |index, value| async move {
+            // Finish later rows first so completion order differs from input order.
+            tokio::time::sleep(std::time::Duration::from_millis(40 - value)).await;
+            Ok((index, value * 2))
+        })
+        .await
+        .unwrap();
+        let expected: Vec<(usize, u64)> = (0..40).map(|i| (i as usize, i * 2)).collect();
+        assert_eq!(outputs, expected);
+    }
+
+    #[tokio::test]
+    async fn run_bounded_passes_the_input_index_to_errors() {
+        let err = run_bounded(vec!["a", "b", "bad", "d"], |index, value| async move {
+            let checked = async {
+                if value == "bad" {
+                    bail!("rejected");
+                }
+                Ok(value)
+            };
+            checked.await.with_context(|| format!("row {index}"))
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(format!("{err:#}"), "row 2: rejected");
+    }
+}
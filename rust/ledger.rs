@@ -1,10 +1,32 @@
-use anyhow::{Context, Result, anyhow};
+use std::{
+    collections::BTreeMap,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use candid::{Decode, Nat};
 use ic_agent::export::Principal;
-use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::{
+    icrc::generic_value::{ICRC3Map, ICRC3Value},
+    icrc1::{
+        account::{Account, Subaccount},
+        transfer::{TransferArg, TransferError},
+    },
+    icrc2::{
+        allowance::{Allowance, AllowanceArgs},
+        approve::{ApproveArgs, ApproveError},
+    },
+    icrc3::blocks::{GetBlocksRequest, GetBlocksResult},
+};
+use tracing::info;
 
-use crate::clients::LEDGER_CANISTER;
+use crate::{clients::LEDGER_CANISTER, error::KinicError};
 
-pub async fn fetch_balance(agent: &ic_agent::Agent) -> Result<u128> {
+pub async fn fetch_balance(
+    agent: &ic_agent::Agent,
+    subaccount: Option<Subaccount>,
+) -> Result<u128> {
     let principal = agent
         .get_principal()
         .map_err(|e| anyhow!("Failed to derive principal for current identity: {e}"))?;
@@ -14,7 +36,7 @@ pub async fn fetch_balance(agent: &ic_agent::Agent) -> Result<u128> {
 
     let account = Account {
         owner: principal,
-        subaccount: None,
+        subaccount,
     };
 
     let payload = candid::encode_one(account)?;
@@ -29,3 +51,646 @@ pub async fn fetch_balance(agent: &ic_agent::Agent) -> Result<u128> {
         candid::decode_one(&response).context("Failed to decode balance response")?;
     Ok(balance)
 }
+
+pub async fn fetch_fee(agent: &ic_agent::Agent) -> Result<Nat> {
+    let ledger_id =
+        Principal::from_text(LEDGER_CANISTER).context("Failed to parse ledger canister id")?;
+    let response = agent
+        .query(&ledger_id, "icrc1_fee")
+        .with_arg(candid::encode_args(())?)
+        .call()
+        .await
+        .context("Failed to query ledger fee")?;
+
+    Decode!(&response, Nat).context("Failed to decode icrc1_fee response")
+}
+
+/// Sends an ICRC-1 transfer and returns the block index. A `Duplicate` rejection means the
+/// same transfer (same `created_at_time`) already landed, so its block index is returned.
+pub async fn transfer(agent: &ic_agent::Agent, args: TransferArg) -> Result<Nat> {
+    let ledger_id =
+        Principal::from_text(LEDGER_CANISTER).context("Failed to parse ledger canister id")?;
+    let payload = candid::encode_one(args)?;
+    let response = agent
+        .update(&ledger_id, "icrc1_transfer")
+        .with_arg(payload)
+        .call_and_wait()
+        .await
+        .context("Failed to call icrc1_transfer")?;
+
+    let result = Decode!(&response, std::result::Result<Nat, TransferError>)
+        .context("Failed to decode icrc1_transfer response")?;
+    match result {
+        Ok(block) => Ok(block),
+        Err(TransferError::Duplicate { duplicate_of }) => {
+            info!(block = %duplicate_of, "transfer already recorded by the ledger");
+            Ok(duplicate_of)
+        }
+        Err(err) => Err(anyhow!(KinicError::Ledger(format!(
+            "Transfer failed: {}",
+            describe_transfer_error(&err)
+        )))),
+    }
+}
+
+pub async fn fetch_allowance(
+    agent: &ic_agent::Agent,
+    account: Account,
+    spender: Account,
+) -> Result<Allowance> {
+    let ledger_id =
+        Principal::from_text(LEDGER_CANISTER).context("Failed to parse ledger canister id")?;
+    let payload = candid::encode_one(AllowanceArgs { account, spender })?;
+    let response = agent
+        .query(&ledger_id, "icrc2_allowance")
+        .with_arg(payload)
+        .call()
+        .await
+        .context("Failed to query icrc2_allowance")?;
+
+    Decode!(&response, Allowance).context("Failed to decode icrc2_allowance response")
+}
+
+/// Resets the allowance granted to `spender` to zero. Costs one ledger fee.
+pub async fn revoke_allowance(
+    agent: &ic_agent::Agent,
+    from_subaccount: Option<Subaccount>,
+    spender: Account,
+) -> Result<Nat> {
+    let ledger_id =
+        Principal::from_text(LEDGER_CANISTER).context("Failed to parse ledger canister id")?;
+    let fee = fetch_fee(agent).await?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64;
+    let args = ApproveArgs {
+        from_subaccount,
+        spender,
+        amount: Nat::from(0u64),
+        expected_allowance: None,
+        expires_at: None,
+        fee: Some(fee),
+        memo: None,
+        created_at_time: Some(now),
+    };
+
+    let payload = candid::encode_one(args)?;
+    let response = agent
+        .update(&ledger_id, "icrc2_approve")
+        .with_arg(payload)
+        .call_and_wait()
+        .await
+        .context("Failed to call icrc2_approve")?;
+
+    Decode!(&response, std::result::Result<Nat, ApproveError>)
+        .context("Failed to decode icrc2_approve response")?
+        .map_err(|e| {
+            anyhow!(KinicError::Ledger(format!(
+                "Failed to revoke allowance: {e}"
+            )))
+        })
+}
+
+/// Returns the number of blocks in the ledger's ICRC-3 log.
+pub async fn fetch_log_length(agent: &ic_agent::Agent) -> Result<u64> {
+    let result = get_blocks(agent, &ledger_principal()?, "icrc3_get_blocks", 0, 0).await?;
+    nat_to_u64(&result.log_length)
+}
+
+/// Upper bound on `icrc3_get_blocks` calls for one [`fetch_blocks`], so a misbehaving ledger or
+/// archive that keeps redirecting cannot loop forever.
+const MAX_BLOCK_CALLS: usize = 256;
+
+/// Fetches blocks `[start, start + length)` via ICRC-3, following archive callbacks for
+/// ranges the ledger no longer holds, including callbacks returned by archives themselves.
+/// Responses are capped, so ranges left out of a reply are requested again until every block
+/// in the range has arrived. Blocks are returned in ascending index order.
+pub async fn fetch_blocks(
+    agent: &ic_agent::Agent,
+    start: u64,
+    length: u64,
+) -> Result<Vec<(u64, ICRC3Value)>> {
+    let ledger_id = ledger_principal()?;
+    let end = start.saturating_add(length);
+    let mut blocks = BTreeMap::new();
+    let mut calls = 0;
+
+    loop {
+        let missing = missing_ranges(&blocks, start, end);
+        if missing.is_empty() {
+            break;
+        }
+        let received = blocks.len();
+        let mut pending: Vec<(Principal, String, u64, u64)> = missing
+            .into_iter()
+            .map(|(from, to)| (ledger_id, "icrc3_get_blocks".to_string(), from, to - from))
+            .collect();
+        while let Some((canister_id, method, from, count)) = pending.pop() {
+            calls += 1;
+            if calls > MAX_BLOCK_CALLS {
+                bail!(
+                    "Gave up fetching ledger blocks {start}..{end} after {MAX_BLOCK_CALLS} calls"
+                );
+            }
+            let result = get_blocks(agent, &canister_id, &method, from, count)
+                .await
+                .with_context(|| format!("Failed to fetch blocks {from}..{}", from + count))?;
+            for archived in result.archived_blocks {
+                for request in archived.args {
+                    pending.push((
+                        archived.callback.canister_id,
+                        archived.callback.method.clone(),
+                        nat_to_u64(&request.start)?,
+                        nat_to_u64(&request.length)?,
+                    ));
+                }
+            }
+            for block in result.blocks {
+                let id = nat_to_u64(&block.id)?;
+                if (start..end).contains(&id) {
+                    blocks.insert(id, block.block);
+                }
+            }
+        }
+        if blocks.len() == received {
+            bail!("Ledger returned no blocks for the missing part of {start}..{end}");
+        }
+    }
+    Ok(blocks.into_iter().collect())
+}
+
+/// Gaps in `[start, end)` not covered by `blocks`, as half-open ranges.
+fn missing_ranges(blocks: &BTreeMap<u64, ICRC3Value>, start: u64, end: u64) -> Vec<(u64, u64)> {
+    let mut missing = Vec::new();
+    let mut next = start;
+    for &id in blocks.range(start..end).map(|(id, _)| id) {
+        if id > next {
+            missing.push((next, id));
+        }
+        next = id + 1;
+    }
+    if next < end {
+        missing.push((next, end));
+    }
+    missing
+}
+
+async fn get_blocks(
+    agent: &ic_agent::Agent,
+    canister_id: &Principal,
+    method: &str,
+    start: u64,
+    length: u64,
+) -> Result<GetBlocksResult> {
+    let request = vec![GetBlocksRequest {
+        start: Nat::from(start),
+        length: Nat::from(length),
+    }];
+    let response = agent
+        .query(canister_id, method)
+        .with_arg(candid::encode_one(request)?)
+        .call()
+        .await
+        .with_context(|| format!("Failed to call {method} on {canister_id}"))?;
+
+    Decode!(&response, GetBlocksResult)
+        .with_context(|| format!("Failed to decode {method} response"))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LedgerOperation {
+    Transfer,
+    Approve,
+    Mint,
+    Burn,
+    Other(String),
+}
+
+/// An ICRC-1/ICRC-2 operation decoded from a generic ICRC-3 block.
+#[derive(Clone, Debug)]
+pub struct LedgerTransaction {
+    pub index: u64,
+    pub timestamp_ns: u64,
+    pub operation: LedgerOperation,
+    pub from: Option<Account>,
+    pub to: Option<Account>,
+    pub spender: Option<Account>,
+    pub amount: u128,
+    pub fee: Option<u128>,
+    pub memo: Option<Vec<u8>>,
+}
+
+impl LedgerTransaction {
+    pub fn from_block(index: u64, block: &ICRC3Value) -> Result<Self> {
+        let ICRC3Value::Map(block) = block else {
+            bail!("Block {index} is not a map");
+        };
+        let tx = match block.get("tx") {
+            Some(ICRC3Value::Map(tx)) => tx,
+            _ => bail!("Block {index} has no transaction"),
+        };
+
+        let op = match (map_text(tx, "op"), map_text(block, "btype")) {
+            (Some(op), _) => op.to_string(),
+            (None, Some(btype)) => btype
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .to_string(),
+            (None, None) => bail!("Block {index} has no operation type"),
+        };
+        let operation = match op.as_str() {
+            "xfer" => LedgerOperation::Transfer,
+            "approve" => LedgerOperation::Approve,
+            "mint" => LedgerOperation::Mint,
+            "burn" => LedgerOperation::Burn,
+            other => LedgerOperation::Other(other.to_string()),
+        };
+
+        let timestamp_ns = match block.get("ts") {
+            Some(ICRC3Value::Nat(ts)) => nat_to_u64(ts)?,
+            _ => 0,
+        };
+        let fee = match tx.get("fee").or_else(|| block.get("fee")) {
+            Some(ICRC3Value::Nat(fee)) => Some(nat_to_u128(fee)?),
+            _ => None,
+        };
+        let amount = match tx.get("amt") {
+            Some(ICRC3Value::Nat(amount)) => nat_to_u128(amount)?,
+            _ => 0,
+        };
+        let memo = match tx.get("memo") {
+            Some(ICRC3Value::Blob(memo)) => Some(memo.to_vec()),
+            _ => None,
+        };
+
+        Ok(Self {
+            index,
+            timestamp_ns,
+            operation,
+            from: map_account(tx, "from")?,
+            to: map_account(tx, "to")?,
+            spender: map_account(tx, "spender")?,
+            amount,
+            fee,
+            memo,
+        })
+    }
+
+    pub fn involves(&self, account: &Account) -> bool {
+        [&self.from, &self.to, &self.spender]
+            .into_iter()
+            .any(|candidate| candidate.as_ref() == Some(account))
+    }
+}
+
+fn map_text<'a>(map: &'a ICRC3Map, key: &str) -> Option<&'a str> {
+    match map.get(key) {
+        Some(ICRC3Value::Text(text)) => Some(text),
+        _ => None,
+    }
+}
+
+fn map_account(map: &ICRC3Map, key: &str) -> Result<Option<Account>> {
+    let Some(ICRC3Value::Array(parts)) = map.get(key) else {
+        return Ok(None);
+    };
+    let owner = match parts.first() {
+        Some(ICRC3Value::Blob(owner)) => Principal::try_from_slice(owner)
+            .map_err(|e| anyhow!("Invalid principal in block {key}: {e}"))?,
+        _ => bail!("Malformed account in block {key}"),
+    };
+    let subaccount = match parts.get(1) {
+        Some(ICRC3Value::Blob(bytes)) => Some(
+            <Subaccount>::try_from(bytes.as_slice())
+                .map_err(|_| anyhow!("Invalid subaccount in block {key}"))?,
+        ),
+        _ => None,
+    };
+    Ok(Some(Account { owner, subaccount }))
+}
+
+fn ledger_principal() -> Result<Principal> {
+    Principal::from_text(LEDGER_CANISTER).context("Failed to parse ledger canister id")
+}
+
+fn nat_to_u64(value: &Nat) -> Result<u64> {
+    u64::try_from(value.0.clone()).map_err(|_| anyhow!("Value {value} does not fit in u64"))
+}
+
+pub fn describe_transfer_error(err: &TransferError) -> String {
+    match err {
+        TransferError::BadFee { expected_fee } => {
+            format!(
+                "the ledger expects a fee of {}",
+                format_nat_kinic(expected_fee)
+            )
+        }
+        TransferError::BadBurn { min_burn_amount } => format!(
+            "burns must be at least {}",
+            format_nat_kinic(min_burn_amount)
+        ),
+        TransferError::InsufficientFunds { balance } => format!(
+            "insufficient funds (balance: {})",
+            format_nat_kinic(balance)
+        ),
+        TransferError::TooOld => {
+            "the transaction is too old; check the local clock and retry".to_string()
+        }
+        TransferError::CreatedInFuture { ledger_time } => format!(
+            "the transaction is dated in the future (ledger time: {ledger_time} ns); check the local clock"
+        ),
+        TransferError::TemporarilyUnavailable => {
+            "the ledger is temporarily unavailable; retry later".to_string()
+        }
+        TransferError::Duplicate { duplicate_of } => {
+            format!("duplicate of the transaction in block {duplicate_of}")
+        }
+        TransferError::GenericError {
+            error_code,
+            message,
+        } => format!("ledger error {error_code}: {message}"),
+    }
+}
+
+/// Parses a transfer recipient: a principal, an ICRC-1 textual account
+/// (`principal-checksum.subaccount`), or `principal.subaccount` with a hex subaccount.
+pub fn parse_account(text: &str) -> Result<Account> {
+    let text = text.trim();
+    if text.len() == 64 && text.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!(KinicError::Validation(
+            "Legacy account identifiers cannot be used: the KINIC ledger only speaks ICRC-1, which addresses an owner principal plus subaccount, and an account id is a one-way hash of those. Ask the recipient for their principal (optionally with .<subaccount>)"
+                .to_string()
+        ));
+    }
+
+    match Account::from_str(text) {
+        Ok(account) => Ok(account),
+        Err(err) => {
+            if let Some((owner, subaccount)) = text.split_once('.')
+                && let Ok(owner) = Principal::from_text(owner)
+            {
+                return Ok(Account {
+                    owner,
+                    subaccount: Some(parse_subaccount(subaccount)?),
+                });
+            }
+            Err(anyhow!(KinicError::Validation(format!(
+                "Invalid account {text}: {err}"
+            ))))
+        }
+    }
+}
+
+/// Parses a hex-encoded subaccount of up to 32 bytes, left-padding with zeroes.
+pub fn parse_subaccount(text: &str) -> Result<Subaccount> {
+    let text = text.trim().trim_start_matches("0x");
+    if text.is_empty() || text.len() > 64 {
+        bail!(KinicError::Validation(
+            "Subaccount must be 1 to 64 hex characters".to_string()
+        ));
+    }
+    let bytes = hex::decode(format!("{text:0>64}")).context("Subaccount must be hex-encoded")?;
+    let mut subaccount = [0u8; 32];
+    subaccount.copy_from_slice(&bytes);
+    Ok(subaccount)
+}
+
+/// Parses a literal `--subaccount` value: hex when prefixed with `0x` or given as exactly 64
+/// hex characters, otherwise a decimal index (stored big-endian in the last bytes).
+pub fn parse_subaccount_arg(text: &str) -> Result<Subaccount> {
+    let text = text.trim();
+    if text.starts_with("0x") || (text.len() == 64 && text.chars().all(|c| c.is_ascii_hexdigit())) {
+        return parse_subaccount(text);
+    }
+    if text.is_empty() || !text.chars().all(|c| c.is_ascii_digit()) {
+        bail!(KinicError::Validation(format!(
+            "Invalid subaccount `{text}`: expected a decimal index, 0x-prefixed hex, or 64 hex characters"
+        )));
+    }
+    let index: u64 = text.parse().map_err(|_| {
+        anyhow!(KinicError::Validation(format!(
+            "Subaccount index `{text}` is too large"
+        )))
+    })?;
+    let mut subaccount = [0u8; 32];
+    subaccount[24..].copy_from_slice(&index.to_be_bytes());
+    Ok(subaccount)
+}
+
+/// Resolves a `--subaccount` value: a literal subaccount, or else a name from `named`.
+pub fn resolve_subaccount(value: &str, named: &BTreeMap<String, String>) -> Result<Subaccount> {
+    if let Some(configured) = named.get(value) {
+        return parse_subaccount_arg(configured)
+            .with_context(|| format!("Invalid subaccount `{value}` in config.json"));
+    }
+    parse_subaccount_arg(value).map_err(|_| {
+        anyhow!(KinicError::Validation(format!(
+            "Unknown subaccount `{value}`: expected a decimal index, 0x-prefixed hex, 64 hex characters, or a name from config.json"
+        )))
+    })
+}
+
+/// Whether `value` is a literal subaccount rather than a name to look up in config.json.
+pub fn is_subaccount_literal(value: &str) -> bool {
+    parse_subaccount_arg(value).is_ok()
+}
+
+/// Parses a decimal KINIC amount such as `1.5` into e8s without going through floats.
+pub fn parse_kinic_amount(text: &str) -> Result<u128> {
+    let text = text.trim();
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        bail!(KinicError::Validation(
+            "Amount must not be empty".to_string()
+        ));
+    }
+    if !whole.chars().all(|c| c.is_ascii_digit()) || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        bail!(KinicError::Validation(
+            "Amount must be a positive decimal number, e.g. 1.5".to_string()
+        ));
+    }
+    if fraction.len() > 8 {
+        bail!(KinicError::Validation(
+            "Amount supports at most 8 decimal places".to_string()
+        ));
+    }
+
+    let whole: u128 = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().context("Amount is too large")?
+    };
+    let fraction: u128 = format!("{fraction:0<8}").parse().unwrap_or(0);
+    whole
+        .checked_mul(E8S_PER_KINIC)
+        .and_then(|value| value.checked_add(fraction))
+        .ok_or_else(|| anyhow!(KinicError::Validation("Amount is too large".to_string())))
+}
+
+fn format_nat_kinic(value: &Nat) -> String {
+    match nat_to_u128(value) {
+        Ok(e8s) => format!("{} KINIC", format_kinic(e8s)),
+        Err(_) => format!("{value} e8s"),
+    }
+}
+
+pub const E8S_PER_KINIC: u128 = 100_000_000;
+
+/// Formats a base-unit amount as an exact decimal KINIC string (8 fractional digits).
+pub fn format_kinic(e8s: u128) -> String {
+    format!("{}.{:08}", e8s / E8S_PER_KINIC, e8s % E8S_PER_KINIC)
+}
+
+/// Converts a base-unit amount to KINIC as a float, for display and the Python bindings.
+pub fn kinic_f64(e8s: u128) -> f64 {
+    e8s as f64 / E8S_PER_KINIC as f64
+}
+
+pub fn nat_to_u128(value: &Nat) -> Result<u128> {
+    u128::try_from(value.0.clone()).map_err(|_| anyhow!("Amount {value} does not fit in u128"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    const OWNER: &str = "rrkah-fqaaa-aaaaa-aaaaq-cai";
+
+    #[test]
+    fn kinic_amount_parses_exact_e8s() {
+        assert_eq!(parse_kinic_amount("1").unwrap(), 100_000_000);
+        assert_eq!(parse_kinic_amount("1.5").unwrap(), 150_000_000);
+        assert_eq!(parse_kinic_amount("0.00000001").unwrap(), 1);
+        assert_eq!(parse_kinic_amount(".5").unwrap(), 50_000_000);
+        assert_eq!(parse_kinic_amount("2.").unwrap(), 200_000_000);
+    }
+
+    #[test]
+    fn kinic_f64_uses_eight_decimals() {
+        assert_eq!(kinic_f64(100_000_000), 1.0);
+        assert_eq!(kinic_f64(250_000_000), 2.5);
+        assert_eq!(kinic_f64(1), 0.00000001);
+        assert_eq!(format_kinic(123_456_789), "1.23456789");
+    }
+
+    #[test]
+    fn kinic_amount_rejects_more_than_eight_decimals() {
+        assert!(parse_kinic_amount("0.000000001").is_err());
+    }
+
+    #[test]
+    fn kinic_amount_rejects_overflow() {
+        let max_whole = u128::MAX / E8S_PER_KINIC;
+        assert!(parse_kinic_amount(&max_whole.to_string()).is_ok());
+        assert!(parse_kinic_amount(&(max_whole + 1).to_string()).is_err());
+        assert!(parse_kinic_amount(&format!("{}0", u128::MAX)).is_err());
+    }
+
+    #[test]
+    fn kinic_amount_rejects_malformed_input() {
+        for text in ["", ".", "-1", "1.2.3", "1e8", "one", " . "] {
+            assert!(
+                parse_kinic_amount(text).is_err(),
+                "{text:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn account_parses_bare_principal() {
+        let account = parse_account(OWNER).unwrap();
+        assert_eq!(account.owner.to_text(), OWNER);
+        assert_eq!(account.subaccount, None);
+    }
+
+    #[test]
+    fn account_parses_principal_dot_subaccount() {
+        let account = parse_account(&format!("{OWNER}.ff")).unwrap();
+        let mut expected = [0u8; 32];
+        expected[31] = 0xff;
+        assert_eq!(account.owner.to_text(), OWNER);
+        assert_eq!(account.subaccount, Some(expected));
+    }
+
+    #[test]
+    fn account_round_trips_icrc1_text() {
+        let mut subaccount = [0u8; 32];
+        subaccount[30] = 1;
+        let account = Account {
+            owner: Principal::from_text(OWNER).unwrap(),
+            subaccount: Some(subaccount),
+        };
+        assert_eq!(parse_account(&account.to_string()).unwrap(), account);
+    }
+
+    #[test]
+    fn account_rejects_bad_checksum() {
+        let mut subaccount = [0u8; 32];
+        subaccount[31] = 7;
+        let text = Account {
+            owner: Principal::from_text(OWNER).unwrap(),
+            subaccount: Some(subaccount),
+        }
+        .to_string();
+        let (owner_and_checksum, sub) = text.rsplit_once('.').unwrap();
+        let (owner, checksum) = owner_and_checksum.rsplit_once('-').unwrap();
+        let corrupted = if checksum.starts_with('a') { "b" } else { "a" };
+        let bad = format!("{owner}-{corrupted}{}.{sub}", &checksum[1..]);
+        assert!(parse_account(&bad).is_err());
+    }
+
+    #[test]
+    fn account_rejects_legacy_account_id() {
+        let err = parse_account(&"ab".repeat(32)).unwrap_err();
+        assert_eq!(ErrorKind::of(&err), ErrorKind::Validation);
+    }
+
+    #[test]
+    fn missing_ranges_finds_gaps() {
+        let mut blocks = BTreeMap::new();
+        assert_eq!(missing_ranges(&blocks, 10, 20), vec![(10, 20)]);
+        for id in [10, 11, 14, 19] {
+            blocks.insert(id, ICRC3Value::Text(String::new()));
+        }
+        assert_eq!(missing_ranges(&blocks, 10, 20), vec![(12, 14), (15, 19)]);
+        assert_eq!(missing_ranges(&blocks, 14, 15), Vec::<(u64, u64)>::new());
+        assert_eq!(missing_ranges(&blocks, 5, 11), vec![(5, 10)]);
+    }
+
+    #[test]
+    fn subaccount_arg_requires_prefix_or_full_length_for_hex() {
+        let mut twelve = [0u8; 32];
+        twelve[31] = 12;
+        assert_eq!(parse_subaccount_arg("12").unwrap(), twelve);
+        let mut hex_ff = [0u8; 32];
+        hex_ff[31] = 0xff;
+        assert_eq!(parse_subaccount_arg("0xff").unwrap(), hex_ff);
+        assert_eq!(
+            parse_subaccount_arg(&format!("{:0>64}", "ff")).unwrap(),
+            hex_ff
+        );
+        assert!(parse_subaccount_arg("ff").is_err());
+        assert!(parse_subaccount_arg("").is_err());
+        assert!(parse_subaccount_arg("99999999999999999999").is_err());
+    }
+
+    #[test]
+    fn subaccount_names_resolve_through_config() {
+        let named = BTreeMap::from([("project-a".to_string(), "0x2a".to_string())]);
+        let mut expected = [0u8; 32];
+        expected[31] = 0x2a;
+        assert_eq!(resolve_subaccount("project-a", &named).unwrap(), expected);
+        assert!(resolve_subaccount("project-b", &named).is_err());
+        assert!(is_subaccount_literal("7"));
+        assert!(!is_subaccount_literal("project-a"));
+    }
+
+    #[test]
+    fn subaccount_left_pads_hex() {
+        let mut expected = [0u8; 32];
+        expected[30] = 0x01;
+        expected[31] = 0x02;
+        assert_eq!(parse_subaccount("0102").unwrap(), expected);
+        assert_eq!(parse_subaccount("0x0102").unwrap(), expected);
+        assert!(parse_subaccount(&"f".repeat(65)).is_err());
+        assert!(parse_subaccount("zz").is_err());
+    }
+}
@@ -1,25 +1,62 @@
+//! Kinic CLI and Rust SDK.
+//!
+//! [`KinicClient`] is the entry point for embedding Kinic in a Rust service: build it once from
+//! an [`AgentFactory`] (or an existing `Agent`) and reuse it for every call.
+//!
+//! ```no_run
+//! # async fn demo() -> anyhow::Result<()> {
+//! use _lib::{AgentFactory, KinicClient};
+//!
+//! let client = KinicClient::connect(&AgentFactory::new(true, "alice")).await?;
+//! let memory = client.create_memory("Notes", "Team notes").await?;
+//! client.insert_text(&memory, "meeting", "We ship on Friday.").await?;
+//! for (score, text) in client.search(&memory, "When do we ship?").await? {
+//!     println!("{score:.4} {text}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Lower-level canister clients live in [`clients`], and the embedding API helpers in
+//! [`embedding`]. [`run`] is the CLI itself.
+
 pub mod agent;
 #[path = "cli_defs.rs"]
 pub mod cli;
-pub(crate) mod clients;
+pub mod clients;
 mod commands;
-mod embedding;
+mod config_store;
+pub mod embedding;
+pub mod error;
 pub(crate) mod identity_store;
+mod kinic_client;
 mod ledger;
 #[cfg(feature = "python-bindings")]
 mod python;
+#[cfg(feature = "python-bindings")]
+mod python_client;
+#[cfg(feature = "python-bindings")]
+mod python_types;
+
+pub use agent::AgentFactory;
+pub use clients::memory::Role;
+pub use commands::ask_ai::AskAiResult;
+pub use error::{ErrorKind, KinicError};
+pub use kinic_client::KinicClient;
 
 use anyhow::Result;
 use clap::Parser;
+use icrc_ledger_types::icrc1::account::Subaccount;
 use tracing::level_filters::LevelFilter;
 use tracing_subscriber::fmt;
 
 use crate::{
-    agent::AgentFactory,
     cli::Cli,
     commands::{CommandContext, run_command},
 };
 
+#[cfg(feature = "python-bindings")]
+use numpy::{AllowTypeChange, PyArray2, PyArrayLike1, PyArrayLike2};
 #[cfg(feature = "python-bindings")]
 use pyo3::{
     exceptions::{PyRuntimeError, PyValueError},
@@ -28,10 +65,14 @@ use pyo3::{
     wrap_pyfunction,
 };
 #[cfg(feature = "python-bindings")]
-use std::path::PathBuf;
+use python_types::SearchHit;
+#[cfg(feature = "python-bindings")]
+use std::{path::PathBuf, sync::OnceLock};
 #[cfg(feature = "python-bindings")]
 use tokio::runtime::Runtime;
 
+const II_ASSET_RESTRICTION: &str = "For security reasons, using a locally hosted origin Internet Identity is not recommended for commands involving asset transfers.";
+
 pub async fn run() -> Result<()> {
     let cli = Cli::parse();
 
@@ -41,73 +82,124 @@ pub async fn run() -> Result<()> {
         _ => LevelFilter::TRACE,
     };
 
-    fmt().with_max_level(max).without_time().try_init().ok();
+    // stdout carries the MCP protocol, so logs must stay on stderr there.
+    if matches!(cli.command, cli::Command::Mcp(_)) {
+        fmt()
+            .with_max_level(max)
+            .without_time()
+            .with_writer(std::io::stderr)
+            .try_init()
+            .ok();
+    } else {
+        fmt().with_max_level(max).without_time().try_init().ok();
+    }
 
     if cli.global.ii
+        && !cfg!(feature = "experimental")
         && matches!(
             cli.command,
-            cli::Command::Create(_) | cli::Command::Balance(_)
+            cli::Command::Create(_)
+                | cli::Command::Balance(_)
+                | cli::Command::Transfer(_)
+                | cli::Command::Allowance(_)
         )
     {
-        if !cfg!(feature = "experimental") {
-            anyhow::bail!(
-                "For security reasons, using a locally hosted origin Internet Identity is not recommended for commands involving asset transfers."
-            );
-        }
+        anyhow::bail!(KinicError::Validation(II_ASSET_RESTRICTION.to_string()));
     }
 
-    let needs_identity_path = matches!(cli.command, cli::Command::Login(_)) || cli.global.ii;
+    // Session management commands never load the delegation, so they work on expired ones too.
+    let manages_session = matches!(
+        cli.command,
+        cli::Command::Login(_) | cli::Command::Logout(_) | cli::Command::Sessions(_)
+    );
+    let needs_identity_path = manages_session || cli.global.ii;
     let identity_path = if needs_identity_path {
-        Some(match cli.global.identity_path.clone() {
-            Some(path) => path,
-            None => identity_store::default_identity_path()?,
+        Some(match (&cli.global.identity_path, &cli.global.session) {
+            (Some(path), _) => path.clone(),
+            (None, Some(name)) => identity_store::session_path(name)?,
+            (None, None) => identity_store::default_identity_path()?,
         })
     } else {
         None
     };
 
-    let agent_factory = if matches!(cli.command, cli::Command::Login(_)) {
+    let agent_factory = if manages_session {
+        AgentFactory::new(cli.global.ic, String::new())
+    } else if cli.global.ii && matches!(cli.command, cli::Command::Whoami(_)) {
+        // whoami loads the delegation itself so it can report how it was verified.
         AgentFactory::new(cli.global.ic, String::new())
     } else if cli.global.ii {
         let path = identity_path
             .clone()
             .ok_or_else(|| anyhow::anyhow!("Identity path is missing"))?;
+        if cli.global.relogin {
+            commands::ii_login::relogin_if_needed(&path).await?;
+        }
         let delegated = identity_store::load_delegated_identity(&path)?;
         AgentFactory::new_with_identity(cli.global.ic, delegated)
     } else {
-        let identity_suffix = cli
-            .global
-            .identity
-            .clone()
-            .ok_or_else(|| anyhow::anyhow!("--identity is required unless --ii is set"))?;
+        let identity_suffix = cli.global.identity.clone().ok_or_else(|| {
+            anyhow::anyhow!(KinicError::Validation(
+                "--identity is required unless --ii is set".to_string()
+            ))
+        })?;
         AgentFactory::new(cli.global.ic, identity_suffix)
     };
 
+    let subaccount = cli
+        .global
+        .subaccount
+        .as_deref()
+        .map(resolve_subaccount_flag)
+        .transpose()?;
+
     let context = CommandContext {
         agent_factory,
         identity_path,
+        subaccount,
     };
 
     run_command(cli.command, context).await
 }
 
+/// Resolves `--subaccount`, reading config.json only when the value names an alias.
+fn resolve_subaccount_flag(value: &str) -> Result<Subaccount> {
+    if ledger::is_subaccount_literal(value) {
+        return ledger::parse_subaccount_arg(value);
+    }
+    let config = config_store::load_config(&config_store::default_config_path()?)?;
+    ledger::resolve_subaccount(value, &config.subaccounts)
+}
+
 #[cfg(feature = "python-bindings")]
 #[pymodule]
 fn _lib(_py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<python_client::Client>()?;
+    m.add_class::<python_types::SearchHit>()?;
+    m.add_class::<python_types::AskAiResult>()?;
     m.add_function(wrap_pyfunction!(greet, m)?)?;
     m.add_function(wrap_pyfunction!(create_memory, m)?)?;
     m.add_function(wrap_pyfunction!(list_memories, m)?)?;
     m.add_function(wrap_pyfunction!(insert_memory, m)?)?;
     m.add_function(wrap_pyfunction!(insert_memory_raw, m)?)?;
+    m.add_function(wrap_pyfunction!(insert_memory_raw_many, m)?)?;
     m.add_function(wrap_pyfunction!(insert_memory_pdf, m)?)?;
     m.add_function(wrap_pyfunction!(search_memories, m)?)?;
     m.add_function(wrap_pyfunction!(search_memories_raw, m)?)?;
+    m.add_function(wrap_pyfunction!(search_memories_raw_many, m)?)?;
     m.add_function(wrap_pyfunction!(tagged_embeddings, m)?)?;
     m.add_function(wrap_pyfunction!(ask_ai, m)?)?;
     m.add_function(wrap_pyfunction!(get_balance, m)?)?;
     m.add_function(wrap_pyfunction!(update_instance, m)?)?;
     m.add_function(wrap_pyfunction!(reset_memory, m)?)?;
     m.add_function(wrap_pyfunction!(add_user, m)?)?;
+    m.add_function(wrap_pyfunction!(login, m)?)?;
+    m.add_function(wrap_pyfunction!(insert_memory_async, m)?)?;
+    m.add_function(wrap_pyfunction!(insert_memory_raw_async, m)?)?;
+    m.add_function(wrap_pyfunction!(search_memories_async, m)?)?;
+    m.add_function(wrap_pyfunction!(tagged_embeddings_async, m)?)?;
+    m.add_function(wrap_pyfunction!(ask_ai_async, m)?)?;
+    add_exceptions(m)?;
     Ok(())
 }
 
@@ -121,32 +213,35 @@ fn greet() -> PyResult<String> {
 #[pyfunction]
 #[pyo3(signature = (identity, name, description, ic=None))]
 fn create_memory(
+    py: Python<'_>,
     identity: &str,
     name: &str,
     description: &str,
     ic: Option<bool>,
 ) -> PyResult<String> {
     let ic = ic.unwrap_or(false);
-    block_on_py(python::create_memory(
-        ic,
-        identity.to_string(),
-        name.to_string(),
-        description.to_string(),
-    ))
+    block_on_py(py, async move {
+        let client = python::connect(ic, identity.to_string()).await?;
+        python::create_memory(&client, name.to_string(), description.to_string()).await
+    })
 }
 
 #[cfg(feature = "python-bindings")]
 #[pyfunction]
 #[pyo3(signature = (identity, ic=None))]
-fn list_memories(identity: &str, ic: Option<bool>) -> PyResult<Vec<String>> {
+fn list_memories(py: Python<'_>, identity: &str, ic: Option<bool>) -> PyResult<Vec<String>> {
     let ic = ic.unwrap_or(false);
-    block_on_py(python::list_memories(ic, identity.to_string()))
+    block_on_py(py, async move {
+        let client = python::connect(ic, identity.to_string()).await?;
+        python::list_memories(&client).await
+    })
 }
 
 #[cfg(feature = "python-bindings")]
 #[pyfunction]
 #[pyo3(signature = (identity, memory_id, tag, text=None, file_path=None, ic=None))]
 fn insert_memory(
+    py: Python<'_>,
     identity: &str,
     memory_id: &str,
     tag: &str,
@@ -162,42 +257,72 @@ fn insert_memory(
 
     let ic = ic.unwrap_or(false);
     let path = file_path.map(PathBuf::from);
-    block_on_py(python::insert_memory(
-        ic,
-        identity.to_string(),
-        memory_id.to_string(),
-        tag.to_string(),
-        text.map(|t| t.to_string()),
-        path,
-    ))
+    block_on_py(py, async move {
+        let client = python::connect(ic, identity.to_string()).await?;
+        python::insert_memory(
+            &client,
+            memory_id.to_string(),
+            tag.to_string(),
+            text.map(|t| t.to_string()),
+            path,
+        )
+        .await
+    })
 }
 
 #[cfg(feature = "python-bindings")]
 #[pyfunction]
 #[pyo3(signature = (identity, memory_id, tag, text, embedding, ic=None))]
 fn insert_memory_raw(
+    py: Python<'_>,
     identity: &str,
     memory_id: &str,
     tag: &str,
     text: &str,
-    embedding: Vec<f32>,
+    embedding: &Bound<'_, PyAny>,
+    ic: Option<bool>,
+) -> PyResult<usize> {
+    let ic = ic.unwrap_or(false);
+    let embedding = vector_from_py(embedding)?;
+    block_on_py(py, async move {
+        let client = python::connect(ic, identity.to_string()).await?;
+        python::insert_memory_raw(
+            &client,
+            memory_id.to_string(),
+            tag.to_string(),
+            text.to_string(),
+            embedding,
+        )
+        .await
+    })
+}
+
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+#[pyo3(signature = (identity, memory_id, tags, texts, embeddings, ic=None))]
+fn insert_memory_raw_many(
+    py: Python<'_>,
+    identity: &str,
+    memory_id: &str,
+    tags: Vec<String>,
+    texts: Vec<String>,
+    embeddings: &Bound<'_, PyAny>,
     ic: Option<bool>,
 ) -> PyResult<usize> {
     let ic = ic.unwrap_or(false);
-    block_on_py(python::insert_memory_raw(
-        ic,
-        identity.to_string(),
-        memory_id.to_string(),
-        tag.to_string(),
-        text.to_string(),
-        embedding,
-    ))
+    let embeddings = matrix_from_py(embeddings)?;
+    block_on_py(py, async move {
+        let client = python::connect(ic, identity.to_string()).await?;
+        python::insert_memory_raw_many(&client, memory_id.to_string(), tags, texts, embeddings)
+            .await
+    })
 }
 
 #[cfg(feature = "python-bindings")]
 #[pyfunction]
 #[pyo3(signature = (identity, memory_id, tag, file_path, ic=None))]
 fn insert_memory_pdf(
+    py: Python<'_>,
     identity: &str,
     memory_id: &str,
     tag: &str,
@@ -205,135 +330,165 @@ fn insert_memory_pdf(
     ic: Option<bool>,
 ) -> PyResult<usize> {
     let ic = ic.unwrap_or(false);
-    block_on_py(python::insert_memory_pdf(
-        ic,
-        identity.to_string(),
-        memory_id.to_string(),
-        tag.to_string(),
-        PathBuf::from(file_path),
-    ))
+    block_on_py(py, async move {
+        let client = python::connect(ic, identity.to_string()).await?;
+        python::insert_memory_pdf(
+            &client,
+            memory_id.to_string(),
+            tag.to_string(),
+            PathBuf::from(file_path),
+        )
+        .await
+    })
 }
 
 #[cfg(feature = "python-bindings")]
 #[pyfunction]
 #[pyo3(signature = (identity, memory_id, query, ic=None))]
 fn search_memories(
+    py: Python<'_>,
     identity: &str,
     memory_id: &str,
     query: &str,
     ic: Option<bool>,
-) -> PyResult<Vec<(f32, String)>> {
+) -> PyResult<Vec<SearchHit>> {
     let ic = ic.unwrap_or(false);
-    block_on_py(python::search_memories(
-        ic,
-        identity.to_string(),
-        memory_id.to_string(),
-        query.to_string(),
-    ))
+    block_on_py(py, async move {
+        let client = python::connect(ic, identity.to_string()).await?;
+        python::search_memories(&client, memory_id.to_string(), query.to_string()).await
+    })
 }
 
 #[cfg(feature = "python-bindings")]
 #[pyfunction]
 #[pyo3(signature = (identity, memory_id, embedding, ic=None))]
 fn search_memories_raw(
+    py: Python<'_>,
     identity: &str,
     memory_id: &str,
-    embedding: Vec<f32>,
+    embedding: &Bound<'_, PyAny>,
     ic: Option<bool>,
-) -> PyResult<Vec<(f32, String)>> {
+) -> PyResult<Vec<SearchHit>> {
     let ic = ic.unwrap_or(false);
-    block_on_py(python::search_memories_raw(
-        ic,
-        identity.to_string(),
-        memory_id.to_string(),
-        embedding,
-    ))
+    let embedding = vector_from_py(embedding)?;
+    block_on_py(py, async move {
+        let client = python::connect(ic, identity.to_string()).await?;
+        python::search_memories_raw(&client, memory_id.to_string(), embedding).await
+    })
+}
+
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+#[pyo3(signature = (identity, memory_id, embeddings, ic=None))]
+fn search_memories_raw_many(
+    py: Python<'_>,
+    identity: &str,
+    memory_id: &str,
+    embeddings: &Bound<'_, PyAny>,
+    ic: Option<bool>,
+) -> PyResult<Vec<Vec<SearchHit>>> {
+    let ic = ic.unwrap_or(false);
+    let embeddings = matrix_from_py(embeddings)?;
+    block_on_py(py, async move {
+        let client = python::connect(ic, identity.to_string()).await?;
+        python::search_memories_raw_many(&client, memory_id.to_string(), embeddings).await
+    })
 }
 
 #[cfg(feature = "python-bindings")]
 #[pyfunction]
 #[pyo3(signature = (identity, memory_id, tag, ic=None))]
-fn tagged_embeddings(
+fn tagged_embeddings<'py>(
+    py: Python<'py>,
     identity: &str,
     memory_id: &str,
     tag: &str,
     ic: Option<bool>,
-) -> PyResult<Vec<Vec<f32>>> {
+) -> PyResult<Bound<'py, PyArray2<f32>>> {
     let ic = ic.unwrap_or(false);
-    block_on_py(python::tagged_embeddings(
-        ic,
-        identity.to_string(),
-        memory_id.to_string(),
-        tag.to_string(),
-    ))
+    let rows = block_on_py(py, async move {
+        let client = python::connect(ic, identity.to_string()).await?;
+        python::tagged_embeddings(&client, memory_id.to_string(), tag.to_string()).await
+    })?;
+    matrix_to_py(py, rows)
 }
 
 #[cfg(feature = "python-bindings")]
 #[pyfunction]
 #[pyo3(signature = (identity, memory_id, query, top_k=None, language=None, ic=None))]
 fn ask_ai(
+    py: Python<'_>,
     identity: &str,
     memory_id: &str,
     query: &str,
     top_k: Option<usize>,
     language: Option<&str>,
     ic: Option<bool>,
-) -> PyResult<(String, String)> {
+) -> PyResult<python_types::AskAiResult> {
     let ic = ic.unwrap_or(false);
     let language = language.map(|s| s.to_string());
-    let result = block_on_py(python::ask_ai(
-        ic,
-        identity.to_string(),
-        memory_id.to_string(),
-        query.to_string(),
-        top_k,
-        language,
-    ))?;
-    Ok((result.prompt, result.response))
+    block_on_py(py, async move {
+        let client = python::connect(ic, identity.to_string()).await?;
+        python::ask_ai(
+            &client,
+            memory_id.to_string(),
+            query.to_string(),
+            top_k,
+            language,
+        )
+        .await
+    })
 }
 
 #[cfg(feature = "python-bindings")]
 #[pyfunction]
 #[pyo3(signature = (identity, ic=None))]
-fn get_balance(identity: &str, ic: Option<bool>) -> PyResult<(u128, f64)> {
+fn get_balance(py: Python<'_>, identity: &str, ic: Option<bool>) -> PyResult<(u128, f64)> {
     let ic = ic.unwrap_or(false);
-    block_on_py(python::balance(ic, identity.to_string()))
+    block_on_py(py, async move {
+        let client = python::connect(ic, identity.to_string()).await?;
+        python::balance(&client).await
+    })
 }
 
 #[cfg(feature = "python-bindings")]
 #[pyfunction]
 #[pyo3(signature = (identity, memory_id, ic=None))]
-fn update_instance(identity: &str, memory_id: &str, ic: Option<bool>) -> PyResult<()> {
+fn update_instance(
+    py: Python<'_>,
+    identity: &str,
+    memory_id: &str,
+    ic: Option<bool>,
+) -> PyResult<()> {
     let ic = ic.unwrap_or(false);
-    block_on_py(python::update_instance(
-        ic,
-        identity.to_string(),
-        memory_id.to_string(),
-    ))
+    block_on_py(py, async move {
+        let client = python::connect(ic, identity.to_string()).await?;
+        python::update_instance(&client, memory_id.to_string()).await
+    })
 }
 
 #[cfg(feature = "python-bindings")]
 #[pyfunction]
 #[pyo3(signature = (identity, memory_id, dim, ic=None))]
 fn reset_memory(
+    py: Python<'_>,
     identity: &str,
     memory_id: &str,
     dim: usize,
     ic: Option<bool>,
 ) -> PyResult<()> {
     let ic = ic.unwrap_or(false);
-    block_on_py(python::reset_memory(
-        ic,
-        identity.to_string(),
-        memory_id.to_string(),
-        dim,
-    ))
+    block_on_py(py, async move {
+        let client = python::connect(ic, identity.to_string()).await?;
+        python::reset_memory(&client, memory_id.to_string(), dim).await
+    })
 }
 
 #[cfg(feature = "python-bindings")]
 #[pyfunction]
 #[pyo3(signature = (identity, memory_id, user_id, role, ic=None))]
 fn add_user(
+    py: Python<'_>,
     identity: &str,
     memory_id: &str,
     user_id: &str,
@@ -341,28 +496,367 @@ fn add_user(
     ic: Option<bool>,
 ) -> PyResult<()> {
     let ic = ic.unwrap_or(false);
-    block_on_py(python::add_user(
-        ic,
-        identity.to_string(),
-        memory_id.to_string(),
-        user_id.to_string(),
-        role.to_string(),
-    ))
+    block_on_py(py, async move {
+        let client = python::connect(ic, identity.to_string()).await?;
+        python::add_user(
+            &client,
+            memory_id.to_string(),
+            user_id.to_string(),
+            role.to_string(),
+        )
+        .await
+    })
 }
 
+/// Runs the Internet Identity browser login and saves the delegation for `Client.from_ii`.
+/// `no_browser` waits for the forwarded callback only; stdin is never read.
 #[cfg(feature = "python-bindings")]
-fn block_on_py<F, T>(future: F) -> PyResult<T>
+#[pyfunction]
+#[pyo3(signature = (
+    identity_path=None,
+    session=None,
+    ttl_hours=None,
+    port=None,
+    provider_url=None,
+    no_browser=false,
+    timeout_secs=None,
+    protect="none",
+))]
+#[allow(clippy::too_many_arguments)]
+fn login(
+    py: Python<'_>,
+    identity_path: Option<PathBuf>,
+    session: Option<String>,
+    ttl_hours: Option<u64>,
+    port: Option<u16>,
+    provider_url: Option<String>,
+    no_browser: bool,
+    timeout_secs: Option<u64>,
+    protect: &str,
+) -> PyResult<String> {
+    use crate::identity_store::ProtectionMode;
+
+    let mut options = commands::ii_login::LoginOptions {
+        no_browser,
+        protection: match protect {
+            "none" => ProtectionMode::None,
+            "keyring" => ProtectionMode::Keyring,
+            "passphrase" => ProtectionMode::Passphrase,
+            other => {
+                return Err(anyhow_to_pyerr(anyhow::anyhow!(KinicError::Validation(
+                    format!("protect must be one of: none, keyring, passphrase (got {other})")
+                ))));
+            }
+        },
+        ..Default::default()
+    };
+    if let Some(ttl_hours) = ttl_hours {
+        options.ttl_hours = ttl_hours;
+    }
+    if let Some(port) = port {
+        options.port = port;
+    }
+    if let Some(provider_url) = provider_url {
+        options.provider_url = provider_url;
+    }
+    if let Some(timeout_secs) = timeout_secs {
+        options.timeout_secs = timeout_secs;
+    }
+    let runtime = shared_runtime()?;
+    py.detach(|| runtime.block_on(python::login(identity_path, session, options)))
+        .map_err(anyhow_to_pyerr)
+}
+
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+#[pyo3(signature = (identity, memory_id, tag, text=None, file_path=None, ic=None))]
+async fn insert_memory_async(
+    identity: String,
+    memory_id: String,
+    tag: String,
+    text: Option<String>,
+    file_path: Option<String>,
+    ic: Option<bool>,
+) -> PyResult<usize> {
+    if text.is_none() && file_path.is_none() {
+        return Err(PyValueError::new_err(
+            "either `text` or `file_path` must be provided",
+        ));
+    }
+
+    let ic = ic.unwrap_or(false);
+    let path = file_path.map(PathBuf::from);
+    spawn_py(shared_runtime()?, async move {
+        let client = python::connect(ic, identity).await?;
+        python::insert_memory(&client, memory_id, tag, text, path).await
+    })
+    .await
+}
+
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+#[pyo3(signature = (identity, memory_id, tag, text, embedding, ic=None))]
+async fn insert_memory_raw_async(
+    identity: String,
+    memory_id: String,
+    tag: String,
+    text: String,
+    embedding: Py<PyAny>,
+    ic: Option<bool>,
+) -> PyResult<usize> {
+    let ic = ic.unwrap_or(false);
+    let embedding = Python::attach(|py| vector_from_py(embedding.bind(py)))?;
+    spawn_py(shared_runtime()?, async move {
+        let client = python::connect(ic, identity).await?;
+        python::insert_memory_raw(&client, memory_id, tag, text, embedding).await
+    })
+    .await
+}
+
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+#[pyo3(signature = (identity, memory_id, query, ic=None))]
+async fn search_memories_async(
+    identity: String,
+    memory_id: String,
+    query: String,
+    ic: Option<bool>,
+) -> PyResult<Vec<SearchHit>> {
+    let ic = ic.unwrap_or(false);
+    spawn_py(shared_runtime()?, async move {
+        let client = python::connect(ic, identity).await?;
+        python::search_memories(&client, memory_id, query).await
+    })
+    .await
+}
+
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+#[pyo3(signature = (identity, memory_id, tag, ic=None))]
+async fn tagged_embeddings_async(
+    identity: String,
+    memory_id: String,
+    tag: String,
+    ic: Option<bool>,
+) -> PyResult<Py<PyArray2<f32>>> {
+    let ic = ic.unwrap_or(false);
+    let rows = spawn_py(shared_runtime()?, async move {
+        let client = python::connect(ic, identity).await?;
+        python::tagged_embeddings(&client, memory_id, tag).await
+    })
+    .await?;
+    Python::attach(|py| matrix_to_py(py, rows).map(Bound::unbind))
+}
+
+#[cfg(feature = "python-bindings")]
+#[pyfunction]
+#[pyo3(signature = (identity, memory_id, query, top_k=None, language=None, ic=None))]
+async fn ask_ai_async(
+    identity: String,
+    memory_id: String,
+    query: String,
+    top_k: Option<usize>,
+    language: Option<String>,
+    ic: Option<bool>,
+) -> PyResult<python_types::AskAiResult> {
+    let ic = ic.unwrap_or(false);
+    spawn_py(shared_runtime()?, async move {
+        let client = python::connect(ic, identity).await?;
+        python::ask_ai(&client, memory_id, query, top_k, language).await
+    })
+    .await
+}
+
+/// Runs `future` on the shared runtime with the GIL released, like `Client::run`.
+#[cfg(feature = "python-bindings")]
+fn block_on_py<F, T>(py: Python<'_>, future: F) -> PyResult<T>
+where
+    F: std::future::Future<Output = Result<T>> + Send,
+    T: Send,
+{
+    let runtime = shared_runtime()?;
+    py.detach(|| runtime.block_on(future))
+        .map_err(anyhow_to_pyerr)
+}
+
+/// Runtime shared by the module-level functions, started on first use.
+#[cfg(feature = "python-bindings")]
+fn shared_runtime() -> PyResult<&'static Runtime> {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    if let Some(runtime) = RUNTIME.get() {
+        return Ok(runtime);
+    }
+    let runtime = new_runtime()?;
+    Ok(RUNTIME.get_or_init(|| runtime))
+}
+
+#[cfg(feature = "python-bindings")]
+fn new_runtime() -> PyResult<Runtime> {
+    Runtime::new()
+        .map_err(|e| PyRuntimeError::new_err(format!("failed to start tokio runtime: {e}")))
+}
+
+/// Starts `future` on `runtime` and returns an awaitable for the Python coroutine. The tokio
+/// runtime drives the I/O; the coroutine only polls the join handle, and dropping it (e.g. when
+/// the asyncio task is cancelled) aborts the call.
+#[cfg(feature = "python-bindings")]
+fn spawn_py<F, T>(
+    runtime: &Runtime,
+    future: F,
+) -> impl std::future::Future<Output = PyResult<T>> + Send + 'static
 where
     F: std::future::Future<Output = Result<T>> + Send + 'static,
     T: Send + 'static,
 {
-    Runtime::new()
-        .map_err(|e| PyRuntimeError::new_err(format!("failed to start tokio runtime: {e}")))?
-        .block_on(future)
-        .map_err(anyhow_to_pyerr)
+    let task = AbortOnDrop(runtime.spawn(future));
+    async move {
+        task.await
+            .map_err(|e| PyRuntimeError::new_err(format!("tokio task failed: {e}")))?
+            .map_err(anyhow_to_pyerr)
+    }
+}
+
+#[cfg(feature = "python-bindings")]
+struct AbortOnDrop<T>(tokio::task::JoinHandle<T>);
+
+#[cfg(feature = "python-bindings")]
+impl<T> std::future::Future for AbortOnDrop<T> {
+    type Output = std::result::Result<T, tokio::task::JoinError>;
+
+    fn poll(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        std::pin::Pin::new(&mut self.0).poll(cx)
+    }
+}
+
+#[cfg(feature = "python-bindings")]
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Copies a 1-D embedding out of a NumPy array (any float dtype) or a sequence of floats. Arrays
+/// are read from their buffer in one pass instead of element by element.
+#[cfg(feature = "python-bindings")]
+fn vector_from_py(value: &Bound<'_, PyAny>) -> PyResult<Vec<f32>> {
+    let array: PyArrayLike1<'_, f32, AllowTypeChange> = value.extract()?;
+    Ok(array.as_array().to_vec())
+}
+
+/// Reads a 2-D batch of embeddings, one per row, like [`vector_from_py`].
+#[cfg(feature = "python-bindings")]
+fn matrix_from_py(value: &Bound<'_, PyAny>) -> PyResult<Vec<Vec<f32>>> {
+    let array: PyArrayLike2<'_, f32, AllowTypeChange> = value.extract()?;
+    Ok(array
+        .as_array()
+        .rows()
+        .into_iter()
+        .map(|row| row.to_vec())
+        .collect())
+}
+
+/// Copies `rows` into one contiguous buffer and hands it to Python as an `(n, dim)` `float32`
+/// array that owns it.
+#[cfg(feature = "python-bindings")]
+fn matrix_to_py(py: Python<'_>, rows: Vec<Vec<f32>>) -> PyResult<Bound<'_, PyArray2<f32>>> {
+    let dim = rows.first().map_or(0, Vec::len);
+    if rows.iter().any(|row| row.len() != dim) {
+        return Err(anyhow_to_pyerr(anyhow::anyhow!(KinicError::Validation(
+            "embeddings have mixed dimensions".to_string()
+        ))));
+    }
+    let shape = (rows.len(), dim);
+    let matrix = numpy::ndarray::Array2::from_shape_vec(shape, rows.concat())
+        .map_err(|e| PyRuntimeError::new_err(format!("failed to build embedding matrix: {e}")))?;
+    Ok(PyArray2::from_owned_array(py, matrix))
+}
+
+/// Python exception hierarchy mirroring [`ErrorKind`]; every class derives from `KinicError`.
+#[cfg(feature = "python-bindings")]
+mod py_errors {
+    use pyo3::{create_exception, exceptions::PyRuntimeError};
+
+    // Subclasses RuntimeError, which the bindings raised for every failure before.
+    create_exception!(
+        kinic_py,
+        KinicError,
+        PyRuntimeError,
+        "Base class of Kinic errors."
+    );
+    create_exception!(
+        kinic_py,
+        AuthError,
+        KinicError,
+        "Credentials are missing, expired or cannot be unlocked."
+    );
+    create_exception!(
+        kinic_py,
+        NetworkError,
+        KinicError,
+        "The replica or another endpoint could not be reached."
+    );
+    create_exception!(
+        kinic_py,
+        CanisterRejectError,
+        KinicError,
+        "A canister rejected the call; `reject_code` holds the IC reject code."
+    );
+    create_exception!(
+        kinic_py,
+        EmbeddingError,
+        KinicError,
+        "The embedding or chat API failed."
+    );
+    create_exception!(
+        kinic_py,
+        ValidationError,
+        KinicError,
+        "Input was rejected before anything was sent."
+    );
+    create_exception!(
+        kinic_py,
+        LedgerError,
+        KinicError,
+        "The ledger refused a transfer or approval."
+    );
+}
+
+#[cfg(feature = "python-bindings")]
+fn add_exceptions(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    use py_errors::*;
+
+    let py = m.py();
+    m.add("KinicError", py.get_type::<KinicError>())?;
+    m.add("AuthError", py.get_type::<AuthError>())?;
+    m.add("NetworkError", py.get_type::<NetworkError>())?;
+    m.add("CanisterRejectError", py.get_type::<CanisterRejectError>())?;
+    m.add("EmbeddingError", py.get_type::<EmbeddingError>())?;
+    m.add("ValidationError", py.get_type::<ValidationError>())?;
+    m.add("LedgerError", py.get_type::<LedgerError>())?;
+    Ok(())
 }
 
 #[cfg(feature = "python-bindings")]
 fn anyhow_to_pyerr(err: anyhow::Error) -> PyErr {
-    PyRuntimeError::new_err(format!("{err:?}"))
+    let message = format!("{err:?}");
+    match ErrorKind::of(&err) {
+        ErrorKind::Auth => py_errors::AuthError::new_err(message),
+        ErrorKind::Network => py_errors::NetworkError::new_err(message),
+        ErrorKind::CanisterReject => {
+            let py_err = py_errors::CanisterRejectError::new_err(message);
+            if let Some(KinicError::CanisterReject { code, .. }) = error::canister_reject(&err) {
+                Python::attach(|py| {
+                    let _ = py_err.value(py).setattr("reject_code", code);
+                });
+            }
+            py_err
+        }
+        ErrorKind::Embedding => py_errors::EmbeddingError::new_err(message),
+        ErrorKind::Validation => py_errors::ValidationError::new_err(message),
+        ErrorKind::Ledger => py_errors::LedgerError::new_err(message),
+        ErrorKind::Other => py_errors::KinicError::new_err(message),
+    }
 }
@@ -1,242 +1,256 @@
-use std::{cmp::Ordering, fs, path::PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
-use anyhow::{Context, Result, anyhow, bail};
+use anyhow::{Context, Result, bail};
 use ic_agent::export::Principal;
-use serde_json::json;
 
 use crate::{
     agent::AgentFactory,
-    clients::{
-        LEDGER_CANISTER,
-        launcher::{LauncherClient, State},
-        memory::MemoryClient,
+    clients::{launcher::State, memory::Role},
+    commands::{
+        acl::parse_user,
+        convert_pdf,
+        ii_login::{self, LoginOptions},
     },
-    commands::ask_ai::{AskAiResult, ask_ai_flow},
-    commands::convert_pdf,
-    embedding::{fetch_embedding, late_chunking},
+    error::KinicError,
+    identity_store,
+    kinic_client::KinicClient,
+    ledger::kinic_f64,
+    python_types::{AskAiResult, SearchHit},
 };
-use icrc_ledger_types::icrc1::account::Account;
 
-pub(crate) async fn create_memory(
+pub(crate) async fn connect(use_mainnet: bool, identity: String) -> Result<KinicClient> {
+    KinicClient::connect(&AgentFactory::new(use_mainnet, identity)).await
+}
+
+/// Factory signing with the stored Internet Identity delegation, resolved like `--ii`:
+/// an explicit path, then a named session, then the default `identity.json`. A missing or
+/// expired delegation is an `Auth` error that names the `kinic_py.login()` call to fix it.
+pub(crate) fn delegated_factory(
     use_mainnet: bool,
-    identity: String,
+    identity_path: Option<PathBuf>,
+    session: Option<String>,
+) -> Result<AgentFactory> {
+    let hint = login_hint(identity_path.as_deref(), session.as_deref());
+    let path = resolve_identity_path(identity_path, session)?;
+    let stored = identity_store::read_stored_identity(&path)
+        .map_err(|err| KinicError::Auth(format!("{err:#}. Call `{hint}` to log in.")))?;
+    if identity_store::remaining_validity_ns(&stored)? == 0 {
+        bail!(KinicError::Auth(format!(
+            "Internet Identity delegation in {} has expired. Call `{hint}` to refresh it.",
+            path.display()
+        )));
+    }
+    let delegated = identity_store::load_delegated_identity(&path)?;
+    Ok(AgentFactory::new_with_identity(use_mainnet, delegated))
+}
+
+/// The `kinic_py.login(...)` call that writes the delegation `from_ii` was asked to load.
+fn login_hint(identity_path: Option<&Path>, session: Option<&str>) -> String {
+    match (identity_path, session) {
+        (Some(path), _) => format!(
+            "kinic_py.login(identity_path={:?})",
+            path.display().to_string()
+        ),
+        (None, Some(name)) => format!("kinic_py.login(session={name:?})"),
+        (None, None) => "kinic_py.login()".to_string(),
+    }
+}
+/// Runs the Internet Identity browser login and returns the principal text.
+pub(crate) async fn login(
+    identity_path: Option<PathBuf>,
+    session: Option<String>,
+    options: LoginOptions,
+) -> Result<String> {
+    let path = resolve_identity_path(identity_path, session)?;
+    let principal = ii_login::login(&path, &options).await?;
+    Ok(principal.to_text())
+}
+
+pub(crate) async fn create_memory(
+    client: &KinicClient,
     name: String,
     description: String,
 ) -> Result<String> {
-    let factory = AgentFactory::new(use_mainnet, identity);
-    let agent = factory.build().await?;
-    let client = LauncherClient::new(agent);
-
-    let price = client.fetch_deployment_price().await?;
-    client.approve_launcher(&price).await?;
-    client.deploy_memory(&name, &description).await
+    let memory = client.create_memory(&name, &description).await?;
+    Ok(memory.to_text())
 }
 
-pub(crate) async fn list_memories(use_mainnet: bool, identity: String) -> Result<Vec<String>> {
-    let factory = AgentFactory::new(use_mainnet, identity);
-    let agent = factory.build().await?;
-    let client = LauncherClient::new(agent);
+pub(crate) async fn list_memories(client: &KinicClient) -> Result<Vec<String>> {
     let states = client.list_memories().await?;
 
     let principals = states
-        .into_iter()
-        .filter_map(|state| state_principal(&state).cloned())
-        .map(|principal| principal.to_text())
+        .iter()
+        .filter_map(State::principal)
+        .map(Principal::to_text)
         .collect();
     Ok(principals)
 }
 
 pub(crate) async fn insert_memory(
-    use_mainnet: bool,
-    identity: String,
+    client: &KinicClient,
     memory_id: String,
     tag: String,
     text: Option<String>,
     file_path: Option<PathBuf>,
 ) -> Result<usize> {
-    let client = build_memory_client(use_mainnet, identity, memory_id).await?;
+    let memory = parse_memory_id(&memory_id)?;
     let content = resolve_insert_content(text, file_path)?;
-    let chunks = late_chunking(&content).await?;
-    let chunk_count = chunks.len();
-
-    for chunk in chunks {
-        let payload = json!({
-            "tag": &tag,
-            "sentence": &chunk.sentence
-        })
-        .to_string();
-        client.insert(chunk.embedding, &payload).await?;
-    }
-
-    Ok(chunk_count)
+    client.insert_text(&memory, &tag, &content).await
 }
 
 pub(crate) async fn insert_memory_raw(
-    use_mainnet: bool,
-    identity: String,
+    client: &KinicClient,
     memory_id: String,
     tag: String,
     text: String,
     embedding: Vec<f32>,
 ) -> Result<usize> {
-    let client = build_memory_client(use_mainnet, identity, memory_id).await?;
-    let payload = json!({
-        "tag": &tag,
-        "sentence": &text
-    })
-    .to_string();
-    client.insert(embedding, &payload).await?;
+    let memory = parse_memory_id(&memory_id)?;
+    client
+        .insert_embedding(&memory, &tag, &text, embedding)
+        .await?;
     Ok(1)
 }
 
+pub(crate) async fn insert_memory_raw_many(
+    client: &KinicClient,
+    memory_id: String,
+    tags: Vec<String>,
+    texts: Vec<String>,
+    embeddings: Vec<Vec<f32>>,
+) -> Result<usize> {
+    let memory = parse_memory_id(&memory_id)?;
+    if tags.len() != embeddings.len() || texts.len() != embeddings.len() {
+        bail!(KinicError::Validation(format!(
+            "tags ({}), texts ({}) and embedding rows ({}) must have the same length",
+            tags.len(),
+            texts.len(),
+            embeddings.len()
+        )));
+    }
+    let entries = tags
+        .into_iter()
+        .zip(texts)
+        .zip(embeddings)
+        .map(|((tag, text), embedding)| (tag, text, embedding))
+        .collect();
+    client.insert_embeddings(&memory, entries).await
+}
+
 pub(crate) async fn insert_memory_pdf(
-    use_mainnet: bool,
-    identity: String,
+    client: &KinicClient,
     memory_id: String,
     tag: String,
     file_path: PathBuf,
 ) -> Result<usize> {
     let markdown = convert_pdf::pdf_to_markdown(&file_path)?;
-    insert_memory(use_mainnet, identity, memory_id, tag, Some(markdown), None).await
+    insert_memory(client, memory_id, tag, Some(markdown), None).await
 }
 
 pub(crate) async fn search_memories(
-    use_mainnet: bool,
-    identity: String,
+    client: &KinicClient,
     memory_id: String,
     query: String,
-) -> Result<Vec<(f32, String)>> {
-    let client = build_memory_client(use_mainnet, identity, memory_id).await?;
-    let embedding = fetch_embedding(&query).await?;
-    let mut results = client.search(embedding).await?;
-    results.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
-    Ok(results)
+) -> Result<Vec<SearchHit>> {
+    let memory = parse_memory_id(&memory_id)?;
+    let results = client.search(&memory, &query).await?;
+    Ok(SearchHit::from_results(&memory_id, results))
 }
 
 pub(crate) async fn search_memories_raw(
-    use_mainnet: bool,
-    identity: String,
+    client: &KinicClient,
     memory_id: String,
     embedding: Vec<f32>,
-) -> Result<Vec<(f32, String)>> {
-    let client = build_memory_client(use_mainnet, identity, memory_id).await?;
-    let mut results = client.search(embedding).await?;
-    results.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
-    Ok(results)
+) -> Result<Vec<SearchHit>> {
+    let memory = parse_memory_id(&memory_id)?;
+    let results = client.search_embedding(&memory, embedding).await?;
+    Ok(SearchHit::from_results(&memory_id, results))
+}
+
+pub(crate) async fn search_memories_raw_many(
+    client: &KinicClient,
+    memory_id: String,
+    embeddings: Vec<Vec<f32>>,
+) -> Result<Vec<Vec<SearchHit>>> {
+    let memory = parse_memory_id(&memory_id)?;
+    let results = client.search_embeddings(&memory, embeddings).await?;
+    Ok(results
+        .into_iter()
+        .map(|rows| SearchHit::from_results(&memory_id, rows))
+        .collect())
 }
 
 pub(crate) async fn tagged_embeddings(
-    use_mainnet: bool,
-    identity: String,
+    client: &KinicClient,
     memory_id: String,
     tag: String,
 ) -> Result<Vec<Vec<f32>>> {
-    let client = build_memory_client(use_mainnet, identity, memory_id).await?;
-    client.tagged_embeddings(tag).await
+    let memory = parse_memory_id(&memory_id)?;
+    client.tagged_embeddings(&memory, &tag).await
 }
 
 pub(crate) async fn ask_ai(
-    use_mainnet: bool,
-    identity: String,
+    client: &KinicClient,
     memory_id: String,
     query: String,
     top_k: Option<usize>,
     language: Option<String>,
 ) -> Result<AskAiResult> {
-    let factory = AgentFactory::new(use_mainnet, identity);
-    let memory = Principal::from_text(memory_id).context("Failed to parse memory canister id")?;
+    let memory = parse_memory_id(&memory_id)?;
     let top_k = top_k.unwrap_or(5);
     let language = language.unwrap_or_else(|| "en".to_string());
-    ask_ai_flow(&factory, &memory, &query, top_k, &language).await
+    let result = client.ask(&memory, &query, top_k, &language).await?;
+    Ok(AskAiResult::new(&memory_id, result))
 }
 
-pub(crate) async fn balance(use_mainnet: bool, identity: String) -> Result<(u128, f64)> {
-    let factory = AgentFactory::new(use_mainnet, identity);
-    let agent = factory.build().await?;
-    let principal = agent
-        .get_principal()
-        .map_err(|e| anyhow!("Failed to derive principal for current identity: {e}"))?;
-
-    let ledger_id =
-        Principal::from_text(LEDGER_CANISTER).context("Failed to parse ledger canister id")?;
-
-    let account = Account {
-        owner: principal,
-        subaccount: None,
-    };
-
-    let payload = candid::encode_one(account)?;
-    let response = agent
-        .query(&ledger_id, "icrc1_balance_of")
-        .with_arg(payload)
-        .call()
-        .await
-        .context("Failed to query ledger balance")?;
-
-    let balance: u128 =
-        candid::decode_one(&response).context("Failed to decode balance response")?;
-    let kinic = balance as f64 / 10_000_000f64;
-
-    Ok((balance, kinic))
+pub(crate) async fn balance(client: &KinicClient) -> Result<(u128, f64)> {
+    let balance = client.balance().await?;
+    Ok((balance, kinic_f64(balance)))
 }
 
 pub(crate) async fn add_user(
-    use_mainnet: bool,
-    identity: String,
+    client: &KinicClient,
     memory_id: String,
     user_id: String,
     role: String,
 ) -> Result<()> {
-    let factory = AgentFactory::new(use_mainnet, identity);
-    let agent = factory.build().await?;
-    let memory = Principal::from_text(memory_id).context("Failed to parse memory canister id")?;
-    let client = MemoryClient::new(agent, memory);
-
-    let role_code = parse_role(&role)?;
-    let principal = parse_principal(&user_id, role_code)?;
-
-    client
-        .add_new_user(principal, role_code)
-        .await
-        .context("Failed to add new user to memory canister")
+    let memory = parse_memory_id(&memory_id)?;
+    let role = role.parse::<Role>()?;
+    let user = parse_user(&user_id, role)?;
+    client.add_user(&memory, user, role).await
 }
 
-pub(crate) async fn update_instance(
-    use_mainnet: bool,
-    identity: String,
-    memory_id: String,
-) -> Result<()> {
-    let factory = AgentFactory::new(use_mainnet, identity);
-    let agent = factory.build().await?;
-    let client = LauncherClient::new(agent);
-    let pid = Principal::from_text(memory_id)
-        .context("Failed to parse canister id for update_instance")?
-        .to_text();
-    client
-        .update_instance(&pid)
-        .await
-        .context("Failed to update instance via launcher canister")
+pub(crate) async fn update_instance(client: &KinicClient, memory_id: String) -> Result<()> {
+    let memory = Principal::from_text(memory_id)
+        .context("Failed to parse canister id for update_instance")?;
+    client.update_instance(&memory).await
 }
 
 pub(crate) async fn reset_memory(
-    use_mainnet: bool,
-    identity: String,
+    client: &KinicClient,
     memory_id: String,
     dim: usize,
 ) -> Result<()> {
-    let client = build_memory_client(use_mainnet, identity, memory_id).await?;
-    client.reset(dim).await
+    let memory = parse_memory_id(&memory_id)?;
+    client.reset(&memory, dim).await
 }
 
-async fn build_memory_client(
-    use_mainnet: bool,
-    identity: String,
-    memory_id: String,
-) -> Result<MemoryClient> {
-    let factory = AgentFactory::new(use_mainnet, identity);
-    let agent = factory.build().await?;
-    let memory = Principal::from_text(memory_id).context("Failed to parse memory canister id")?;
-    Ok(MemoryClient::new(agent, memory))
+fn resolve_identity_path(
+    identity_path: Option<PathBuf>,
+    session: Option<String>,
+) -> Result<PathBuf> {
+    match (identity_path, session) {
+        (Some(path), _) => Ok(path),
+        (None, Some(name)) => identity_store::session_path(&name),
+        (None, None) => identity_store::default_identity_path(),
+    }
+}
+
+fn parse_memory_id(memory_id: &str) -> Result<Principal> {
+    Principal::from_text(memory_id).context("Failed to parse memory canister id")
 }
 
 fn resolve_insert_content(text: Option<String>, file_path: Option<PathBuf>) -> Result<String> {
@@ -251,32 +265,3 @@ fn resolve_insert_content(text: Option<String>, file_path: Option<PathBuf>) -> R
 
     bail!("either text or file_path must be provided");
 }
-
-fn parse_role(role: &str) -> Result<u8> {
-    match role.to_lowercase().as_str() {
-        "admin" => Ok(1),
-        "writer" => Ok(2),
-        "reader" => Ok(3),
-        _ => bail!("role must be one of: admin, writer, reader"),
-    }
-}
-
-fn parse_principal(user_id: &str, role_code: u8) -> Result<Principal> {
-    if user_id == "anonymous" {
-        if role_code == 1 {
-            bail!("cannot grant admin role to anonymous");
-        }
-        Ok(Principal::anonymous())
-    } else {
-        Principal::from_text(user_id).with_context(|| format!("invalid principal text: {user_id}"))
-    }
-}
-
-fn state_principal(state: &State) -> Option<&Principal> {
-    match state {
-        State::Installation(principal, _)
-        | State::SettingUp(principal)
-        | State::Running(principal) => Some(principal),
-        _ => None,
-    }
-}
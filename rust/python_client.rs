@@ -0,0 +1,380 @@
+//! rust/python_client.rs
+//! Where: `kinic_py._lib.Client`, part of the Python bindings.
+//! What: A Python class owning one tokio runtime and one connected `KinicClient`.
+//! Why: The module-level functions rebuild the agent (keyring read, root key fetch) on every
+//! call; long-running notebooks and batch inserts should pay that cost once.
+
+use std::{fs, future::Future, path::PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+use numpy::PyArray2;
+use pyo3::{exceptions::PyValueError, prelude::*};
+use tokio::runtime::Runtime;
+
+use crate::{
+    II_ASSET_RESTRICTION,
+    agent::AgentFactory,
+    anyhow_to_pyerr,
+    error::KinicError,
+    kinic_client::KinicClient,
+    matrix_from_py, matrix_to_py, new_runtime, python,
+    python_types::{AskAiResult, SearchHit},
+    spawn_py, vector_from_py,
+};
+
+/// Connected Kinic client; methods mirror the module-level functions without `identity`/`ic`.
+/// Build it from a keyring identity name, or with `from_ii`, `from_pem`, `from_pem_file` or
+/// `anonymous`.
+/// The `*_async` methods return awaitables that share this client's agent and runtime.
+#[pyclass(module = "kinic_py", frozen)]
+pub(crate) struct Client {
+    runtime: Runtime,
+    client: KinicClient,
+    /// Signs with an Internet Identity delegation, which the CLI keeps away from asset transfers.
+    delegated: bool,
+}
+
+impl Client {
+    fn connect(py: Python<'_>, factory: AgentFactory, delegated: bool) -> PyResult<Self> {
+        let runtime = new_runtime()?;
+        let client = py
+            .detach(|| runtime.block_on(KinicClient::connect(&factory)))
+            .map_err(anyhow_to_pyerr)?;
+        Ok(Self {
+            runtime,
+            client,
+            delegated,
+        })
+    }
+
+    /// Mirrors the CLI, which refuses `--ii` for commands that move tokens.
+    fn ensure_transfers_allowed(&self) -> PyResult<()> {
+        if self.delegated && !cfg!(feature = "experimental") {
+            return Err(anyhow_to_pyerr(anyhow!(KinicError::Validation(
+                II_ASSET_RESTRICTION.to_string()
+            ))));
+        }
+        Ok(())
+    }
+
+    /// Runs `call` to completion on this client's runtime with the GIL released.
+    fn run<'a, F, Fut, T>(&'a self, py: Python<'_>, call: F) -> PyResult<T>
+    where
+        F: FnOnce(&'a KinicClient) -> Fut + Send,
+        Fut: Future<Output = Result<T>>,
+        T: Send,
+    {
+        py.detach(|| self.runtime.block_on(call(&self.client)))
+            .map_err(anyhow_to_pyerr)
+    }
+}
+
+#[pymethods]
+impl Client {
+    /// Loads `identity` from the keyring and builds the agent once.
+    #[new]
+    #[pyo3(signature = (identity, ic=None))]
+    fn new(py: Python<'_>, identity: &str, ic: Option<bool>) -> PyResult<Self> {
+        let factory = AgentFactory::new(ic.unwrap_or(false), identity);
+        Self::connect(py, factory, false)
+    }
+
+    /// Signs with a stored Internet Identity delegation (see `login`). Without arguments,
+    /// `~/.config/kinic/identity.json` is used; `session` picks a named session instead.
+    #[staticmethod]
+    #[pyo3(signature = (identity_path=None, session=None, ic=None))]
+    fn from_ii(
+        py: Python<'_>,
+        identity_path: Option<PathBuf>,
+        session: Option<String>,
+        ic: Option<bool>,
+    ) -> PyResult<Self> {
+        // Unlocking the session key may block on the keyring or a passphrase prompt.
+        let factory = py
+            .detach(|| python::delegated_factory(ic.unwrap_or(false), identity_path, session))
+            .map_err(anyhow_to_pyerr)?;
+        Self::connect(py, factory, true)
+    }
+
+    /// Signs with a PEM private key given as text (Ed25519 or secp256k1, as written by dfx).
+    #[staticmethod]
+    #[pyo3(signature = (pem, ic=None))]
+    fn from_pem(py: Python<'_>, pem: &str, ic: Option<bool>) -> PyResult<Self> {
+        let factory = AgentFactory::from_pem(ic.unwrap_or(false), pem).map_err(anyhow_to_pyerr)?;
+        Self::connect(py, factory, false)
+    }
+
+    /// Signs with the PEM private key stored at `path`.
+    #[staticmethod]
+    #[pyo3(signature = (path, ic=None))]
+    fn from_pem_file(py: Python<'_>, path: PathBuf, ic: Option<bool>) -> PyResult<Self> {
+        let pem = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read PEM file {}", path.display()))
+            .map_err(anyhow_to_pyerr)?;
+        Self::from_pem(py, &pem, ic)
+    }
+
+    /// Unauthenticated client; works with memories that grant `anonymous` a role.
+    #[staticmethod]
+    #[pyo3(signature = (ic=None))]
+    fn anonymous(py: Python<'_>, ic: Option<bool>) -> PyResult<Self> {
+        Self::connect(py, AgentFactory::anonymous(ic.unwrap_or(false)), false)
+    }
+
+    /// Principal text of the identity this client signs with.
+    #[getter]
+    fn principal(&self) -> PyResult<String> {
+        self.client
+            .principal()
+            .map(|principal| principal.to_text())
+            .map_err(anyhow_to_pyerr)
+    }
+
+    fn create_memory(&self, py: Python<'_>, name: &str, description: &str) -> PyResult<String> {
+        self.ensure_transfers_allowed()?;
+        let (name, description) = (name.to_string(), description.to_string());
+        self.run(py, |client| {
+            python::create_memory(client, name, description)
+        })
+    }
+
+    fn list_memories(&self, py: Python<'_>) -> PyResult<Vec<String>> {
+        self.run(py, python::list_memories)
+    }
+
+    #[pyo3(signature = (memory_id, tag, text=None, file_path=None))]
+    fn insert_memory(
+        &self,
+        py: Python<'_>,
+        memory_id: &str,
+        tag: &str,
+        text: Option<&str>,
+        file_path: Option<&str>,
+    ) -> PyResult<usize> {
+        if text.is_none() && file_path.is_none() {
+            return Err(PyValueError::new_err(
+                "either `text` or `file_path` must be provided",
+            ));
+        }
+        let (memory_id, tag) = (memory_id.to_string(), tag.to_string());
+        let text = text.map(str::to_string);
+        let path = file_path.map(PathBuf::from);
+        self.run(py, |client| {
+            python::insert_memory(client, memory_id, tag, text, path)
+        })
+    }
+
+    fn insert_memory_raw(
+        &self,
+        py: Python<'_>,
+        memory_id: &str,
+        tag: &str,
+        text: &str,
+        embedding: &Bound<'_, PyAny>,
+    ) -> PyResult<usize> {
+        let embedding = vector_from_py(embedding)?;
+        let (memory_id, tag, text) = (memory_id.to_string(), tag.to_string(), text.to_string());
+        self.run(py, |client| {
+            python::insert_memory_raw(client, memory_id, tag, text, embedding)
+        })
+    }
+
+    /// Inserts one entry per row of `embeddings` (an `(n, dim)` array), with `tags[i]` and
+    /// `texts[i]` for row `i`. Several inserts are kept in flight at once.
+    fn insert_memory_raw_many(
+        &self,
+        py: Python<'_>,
+        memory_id: &str,
+        tags: Vec<String>,
+        texts: Vec<String>,
+        embeddings: &Bound<'_, PyAny>,
+    ) -> PyResult<usize> {
+        let embeddings = matrix_from_py(embeddings)?;
+        let memory_id = memory_id.to_string();
+        self.run(py, |client| {
+            python::insert_memory_raw_many(client, memory_id, tags, texts, embeddings)
+        })
+    }
+
+    fn insert_memory_pdf(
+        &self,
+        py: Python<'_>,
+        memory_id: &str,
+        tag: &str,
+        file_path: &str,
+    ) -> PyResult<usize> {
+        let (memory_id, tag) = (memory_id.to_string(), tag.to_string());
+        let path = PathBuf::from(file_path);
+        self.run(py, |client| {
+            python::insert_memory_pdf(client, memory_id, tag, path)
+        })
+    }
+
+    fn search_memories(
+        &self,
+        py: Python<'_>,
+        memory_id: &str,
+        query: &str,
+    ) -> PyResult<Vec<SearchHit>> {
+        let (memory_id, query) = (memory_id.to_string(), query.to_string());
+        self.run(py, |client| {
+            python::search_memories(client, memory_id, query)
+        })
+    }
+
+    fn search_memories_raw(
+        &self,
+        py: Python<'_>,
+        memory_id: &str,
+        embedding: &Bound<'_, PyAny>,
+    ) -> PyResult<Vec<SearchHit>> {
+        let embedding = vector_from_py(embedding)?;
+        let memory_id = memory_id.to_string();
+        self.run(py, |client| {
+            python::search_memories_raw(client, memory_id, embedding)
+        })
+    }
+
+    /// Searches with every row of `embeddings`; returns one result list per row, in order.
+    fn search_memories_raw_many(
+        &self,
+        py: Python<'_>,
+        memory_id: &str,
+        embeddings: &Bound<'_, PyAny>,
+    ) -> PyResult<Vec<Vec<SearchHit>>> {
+        let embeddings = matrix_from_py(embeddings)?;
+        let memory_id = memory_id.to_string();
+        self.run(py, |client| {
+            python::search_memories_raw_many(client, memory_id, embeddings)
+        })
+    }
+
+    /// Embeddings stored under `tag` as an `(n, dim)` `float32` array.
+    fn tagged_embeddings<'py>(
+        &self,
+        py: Python<'py>,
+        memory_id: &str,
+        tag: &str,
+    ) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        let (memory_id, tag) = (memory_id.to_string(), tag.to_string());
+        let rows = self.run(py, |client| {
+            python::tagged_embeddings(client, memory_id, tag)
+        })?;
+        matrix_to_py(py, rows)
+    }
+
+    #[pyo3(signature = (memory_id, query, top_k=None, language=None))]
+    fn ask_ai(
+        &self,
+        py: Python<'_>,
+        memory_id: &str,
+        query: &str,
+        top_k: Option<usize>,
+        language: Option<&str>,
+    ) -> PyResult<AskAiResult> {
+        let (memory_id, query) = (memory_id.to_string(), query.to_string());
+        let language = language.map(str::to_string);
+        self.run(py, |client| {
+            python::ask_ai(client, memory_id, query, top_k, language)
+        })
+    }
+
+    fn get_balance(&self, py: Python<'_>) -> PyResult<(u128, f64)> {
+        self.ensure_transfers_allowed()?;
+        self.run(py, python::balance)
+    }
+
+    fn update_instance(&self, py: Python<'_>, memory_id: &str) -> PyResult<()> {
+        let memory_id = memory_id.to_string();
+        self.run(py, |client| python::update_instance(client, memory_id))
+    }
+
+    fn reset_memory(&self, py: Python<'_>, memory_id: &str, dim: usize) -> PyResult<()> {
+        let memory_id = memory_id.to_string();
+        self.run(py, |client| python::reset_memory(client, memory_id, dim))
+    }
+
+    fn add_user(&self, py: Python<'_>, memory_id: &str, user_id: &str, role: &str) -> PyResult<()> {
+        let (memory_id, user_id, role) =
+            (memory_id.to_string(), user_id.to_string(), role.to_string());
+        self.run(py, |client| {
+            python::add_user(client, memory_id, user_id, role)
+        })
+    }
+
+    #[pyo3(signature = (memory_id, tag, text=None, file_path=None))]
+    async fn insert_memory_async(
+        &self,
+        memory_id: String,
+        tag: String,
+        text: Option<String>,
+        file_path: Option<String>,
+    ) -> PyResult<usize> {
+        if text.is_none() && file_path.is_none() {
+            return Err(PyValueError::new_err(
+                "either `text` or `file_path` must be provided",
+            ));
+        }
+        let client = self.client.clone();
+        let path = file_path.map(PathBuf::from);
+        spawn_py(&self.runtime, async move {
+            python::insert_memory(&client, memory_id, tag, text, path).await
+        })
+        .await
+    }
+
+    async fn insert_memory_raw_async(
+        &self,
+        memory_id: String,
+        tag: String,
+        text: String,
+        embedding: Py<PyAny>,
+    ) -> PyResult<usize> {
+        let embedding = Python::attach(|py| vector_from_py(embedding.bind(py)))?;
+        let client = self.client.clone();
+        spawn_py(&self.runtime, async move {
+            python::insert_memory_raw(&client, memory_id, tag, text, embedding).await
+        })
+        .await
+    }
+
+    async fn search_memories_async(
+        &self,
+        memory_id: String,
+        query: String,
+    ) -> PyResult<Vec<SearchHit>> {
+        let client = self.client.clone();
+        spawn_py(&self.runtime, async move {
+            python::search_memories(&client, memory_id, query).await
+        })
+        .await
+    }
+
+    async fn tagged_embeddings_async(
+        &self,
+        memory_id: String,
+        tag: String,
+    ) -> PyResult<Py<PyArray2<f32>>> {
+        let client = self.client.clone();
+        let rows = spawn_py(&self.runtime, async move {
+            python::tagged_embeddings(&client, memory_id, tag).await
+        })
+        .await?;
+        Python::attach(|py| matrix_to_py(py, rows).map(Bound::unbind))
+    }
+
+    #[pyo3(signature = (memory_id, query, top_k=None, language=None))]
+    async fn ask_ai_async(
+        &self,
+        memory_id: String,
+        query: String,
+        top_k: Option<usize>,
+        language: Option<String>,
+    ) -> PyResult<AskAiResult> {
+        let client = self.client.clone();
+        spawn_py(&self.runtime, async move {
+            python::ask_ai(&client, memory_id, query, top_k, language).await
+        })
+        .await
+    }
+}
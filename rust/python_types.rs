@@ -0,0 +1,99 @@
+//! rust/python_types.rs
+//! Where: `kinic_py._lib.SearchHit` and `kinic_py._lib.AskAiResult`, part of the Python bindings.
+//! What: Read-only result objects returned by the search and Ask AI functions.
+//! Why: Callers otherwise get the stored `{"tag","sentence"}` JSON as an opaque string and
+//! lose the Ask AI context metadata.
+
+use pyo3::prelude::*;
+
+use crate::{commands::ask_ai::AskAiResult as AskAiOutput, kinic_client::split_tagged_text};
+
+/// One search result. `tag` is `None` when the payload was not written by Kinic.
+#[pyclass(module = "kinic_py", frozen, get_all)]
+#[derive(Clone)]
+pub(crate) struct SearchHit {
+    score: f32,
+    tag: Option<String>,
+    sentence: String,
+    memory_id: String,
+    /// Payload exactly as stored in the memory canister.
+    raw: String,
+}
+
+impl SearchHit {
+    pub(crate) fn new(memory_id: &str, score: f32, raw: String) -> Self {
+        let (tag, sentence) = split_tagged_text(raw.clone());
+        Self {
+            score,
+            tag,
+            sentence,
+            memory_id: memory_id.to_string(),
+            raw,
+        }
+    }
+
+    pub(crate) fn from_results(memory_id: &str, results: Vec<(f32, String)>) -> Vec<Self> {
+        results
+            .into_iter()
+            .map(|(score, raw)| Self::new(memory_id, score, raw))
+            .collect()
+    }
+}
+
+#[pymethods]
+impl SearchHit {
+    fn __repr__(&self) -> String {
+        format!(
+            "SearchHit(score={:.4}, tag={}, sentence={:?}, memory_id={:?})",
+            self.score,
+            self.tag
+                .as_deref()
+                .map_or_else(|| "None".to_string(), |tag| format!("{tag:?}")),
+            clip(&self.sentence, 60),
+            self.memory_id,
+        )
+    }
+}
+
+/// Ask AI answer with the prompt sent to the LLM and the hits it was built from.
+#[pyclass(module = "kinic_py", name = "AskAiResult", frozen, get_all)]
+pub(crate) struct AskAiResult {
+    prompt: String,
+    response: String,
+    /// Best hits used as context, highest score first.
+    context: Vec<SearchHit>,
+    /// Number of search results the memory returned, before `top_k` was applied.
+    context_count: usize,
+    top_k_used: usize,
+}
+
+impl AskAiResult {
+    pub(crate) fn new(memory_id: &str, output: AskAiOutput) -> Self {
+        Self {
+            prompt: output.prompt,
+            response: output.response,
+            context: SearchHit::from_results(memory_id, output.context),
+            context_count: output.context_count,
+            top_k_used: output.top_k_used,
+        }
+    }
+}
+
+#[pymethods]
+impl AskAiResult {
+    fn __repr__(&self) -> String {
+        format!(
+            "AskAiResult(response={:?}, context_count={}, top_k_used={})",
+            clip(&self.response, 60),
+            self.context_count,
+            self.top_k_used,
+        )
+    }
+}
+
+fn clip(s: &str, max: usize) -> String {
+    match s.char_indices().nth(max) {
+        Some((end, _)) => format!("{}...", &s[..end]),
+        None => s.to_string(),
+    }
+}